@@ -0,0 +1,182 @@
+//! Errors returned to GraphQL clients
+//!
+//! Upstream errors can include the request URL, and with it the API key, so clients only get a
+//! stable code in the error `extensions` and a safe message. The full error goes to the log.
+use juniper::{graphql_value, FieldError, IntoFieldError, ScalarValue};
+use log::{debug, error};
+use thiserror::Error;
+
+use crate::auth::Scope;
+use crate::met_office::{LocationConversionError, MetApiError};
+use crate::pollen::PollenError;
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("Met Office error: {0:?}")]
+    MetApi(#[from] MetApiError),
+    #[error("pollen error: {0:?}")]
+    Pollen(#[from] PollenError),
+    #[error("invalid location: {0:?}")]
+    InvalidLocation(#[from] LocationConversionError),
+    #[error("the {0} scope is required")]
+    Forbidden(Scope),
+}
+
+/// Stable codes clients can match on, sent as `extensions.code`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorCode {
+    MissingApiKey,
+    InvalidApiKey,
+    RateLimited,
+    QuotaExhausted,
+    NotFound,
+    UpstreamUnavailable,
+    UpstreamInvalidResponse,
+    UpstreamInvalidData,
+    PollenUnavailable,
+    PollenInvalidResponse,
+    InvalidLocation,
+    Forbidden,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::MissingApiKey => "MISSING_API_KEY",
+            ErrorCode::InvalidApiKey => "INVALID_API_KEY",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::QuotaExhausted => "QUOTA_EXHAUSTED",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::UpstreamUnavailable => "UPSTREAM_UNAVAILABLE",
+            ErrorCode::UpstreamInvalidResponse => "UPSTREAM_INVALID_RESPONSE",
+            ErrorCode::UpstreamInvalidData => "UPSTREAM_INVALID_DATA",
+            ErrorCode::PollenUnavailable => "POLLEN_UNAVAILABLE",
+            ErrorCode::PollenInvalidResponse => "POLLEN_INVALID_RESPONSE",
+            ErrorCode::InvalidLocation => "INVALID_LOCATION",
+            ErrorCode::Forbidden => "FORBIDDEN",
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            ErrorCode::MissingApiKey => "The server has no Met Office API key configured",
+            ErrorCode::InvalidApiKey => "The Met Office API key was rejected",
+            ErrorCode::RateLimited => "The DataPoint request limit was reached, try again later",
+            ErrorCode::QuotaExhausted => "The DataPoint request budget is used up, try again later",
+            ErrorCode::NotFound => "DataPoint has no data for the request",
+            ErrorCode::UpstreamUnavailable => {
+                "The Met Office DataPoint service could not be reached"
+            }
+            ErrorCode::UpstreamInvalidResponse => {
+                "DataPoint returned a response that could not be read"
+            }
+            ErrorCode::UpstreamInvalidData => "DataPoint returned data that could not be converted",
+            ErrorCode::PollenUnavailable => "The pollen forecast could not be fetched",
+            ErrorCode::PollenInvalidResponse => "The pollen forecast page could not be read",
+            ErrorCode::InvalidLocation => "Invalid location",
+            ErrorCode::Forbidden => "The access token does not allow this",
+        }
+    }
+}
+
+fn met_api_code(error: &MetApiError) -> ErrorCode {
+    match error {
+        MetApiError::MissingApiKey => ErrorCode::MissingApiKey,
+        MetApiError::InvalidApiKey(_) => ErrorCode::InvalidApiKey,
+        MetApiError::RateLimited(_) => ErrorCode::RateLimited,
+        MetApiError::QuotaExhausted => ErrorCode::QuotaExhausted,
+        MetApiError::NotFound(_) => ErrorCode::NotFound,
+        MetApiError::HttpError(_)
+        | MetApiError::UpstreamUnavailable(..)
+        | MetApiError::CircuitOpen => ErrorCode::UpstreamUnavailable,
+        MetApiError::ParseError(_) | MetApiError::IoError(_) | MetApiError::NotAnImage(_) => {
+            ErrorCode::UpstreamInvalidResponse
+        }
+        MetApiError::ForecastConversionError(_)
+        | MetApiError::ObservationConversionError(_)
+        | MetApiError::MountainForecastConversionError(_)
+        | MetApiError::UkExtremesConversionError(_)
+        | MetApiError::NumberParseError(_) => ErrorCode::UpstreamInvalidData,
+        MetApiError::Shared(error) => met_api_code(error),
+    }
+}
+
+impl ApiError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ApiError::MetApi(error) => met_api_code(error),
+            ApiError::Pollen(error) => match error {
+                PollenError::Fetch(_) => ErrorCode::PollenUnavailable,
+                PollenError::PollenLevelConversionError(_)
+                | PollenError::DateTimeParseError(_)
+                | PollenError::MissingElement(_)
+                | PollenError::NoRegions => ErrorCode::PollenInvalidResponse,
+            },
+            ApiError::InvalidLocation(_) => ErrorCode::InvalidLocation,
+            ApiError::Forbidden(_) => ErrorCode::Forbidden,
+        }
+    }
+
+    /// A message that is safe to show to clients, only client input is repeated back
+    pub fn message(&self) -> String {
+        match self {
+            ApiError::InvalidLocation(error) => error.to_string(),
+            ApiError::Forbidden(scope) => format!("The access token needs the {} scope", scope),
+            _ => self.code().message().to_string(),
+        }
+    }
+}
+
+impl<S: ScalarValue> IntoFieldError<S> for ApiError {
+    fn into_field_error(self) -> FieldError<S> {
+        match self {
+            ApiError::InvalidLocation(_) | ApiError::Forbidden(_) => debug!("{}", self),
+            _ => error!("{}", self),
+        }
+        let code = self.code().as_str();
+        FieldError::new(self.message(), graphql_value!({ "code": code }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::met_office::LocationId;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    fn code(error: &FieldError) -> Option<&str> {
+        error
+            .extensions()
+            .as_object_value()
+            .and_then(|extensions| extensions.get_field_value("code"))
+            .and_then(|code| code.as_scalar_value::<String>())
+            .map(String::as_str)
+    }
+
+    #[test]
+    fn test_upstream_error_is_not_leaked() {
+        let parse_error = serde_json::from_str::<u32>("<html>key=secret</html>").unwrap_err();
+        let error = ApiError::from(MetApiError::from(parse_error)).into_field_error();
+        assert_eq!(code(&error), Some("UPSTREAM_INVALID_RESPONSE"));
+        assert!(!error.message().contains("secret"));
+    }
+
+    #[test]
+    fn test_shared_error_keeps_its_code() {
+        let error = MetApiError::Shared(Arc::new(MetApiError::NotFound(String::new())));
+        assert_eq!(ApiError::from(error).code().as_str(), "NOT_FOUND");
+    }
+
+    #[test]
+    fn test_invalid_location() {
+        let error = ApiError::from(LocationId::from_str("x").unwrap_err()).into_field_error();
+        assert_eq!(code(&error), Some("INVALID_LOCATION"));
+        assert_eq!(
+            error.message(),
+            "invalid location, expected \"all\" or positive integer, found x"
+        );
+    }
+}
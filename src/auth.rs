@@ -0,0 +1,270 @@
+//! Bearer token authentication
+//!
+//! Tokens are issued by whoever runs the server, each with the scopes it may use. Requests
+//! without a token are anonymous and only reach unscoped fields, requests with an unknown token
+//! are rejected. Authentication can only be turned off explicitly, which allows every scope.
+mod scope;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use actix_web::dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::ErrorUnauthorized;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use futures::future::{err, ok, Either, Ready};
+use thiserror::Error;
+
+pub use scope::{Scope, ScopeConversionError};
+
+#[derive(Debug, Error)]
+pub enum AccessTokenError {
+    #[error("expected `name token scopes`, found {0}")]
+    InvalidDefinition(String),
+    #[error("invalid scope: {0}")]
+    InvalidScope(#[from] ScopeConversionError),
+}
+
+/// Who made a request and what they may do
+#[derive(Clone, Debug, PartialEq)]
+pub struct Principal {
+    pub name: String,
+    scopes: HashSet<Scope>,
+}
+
+impl Principal {
+    pub fn anonymous() -> Principal {
+        Principal {
+            name: "anonymous".to_string(),
+            scopes: HashSet::new(),
+        }
+    }
+
+    fn unrestricted() -> Principal {
+        Principal {
+            name: "unrestricted".to_string(),
+            scopes: [Scope::Admin].iter().copied().collect(),
+        }
+    }
+
+    /// Admin tokens may use every scope
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope) || self.scopes.contains(&Scope::Admin)
+    }
+}
+
+/// Reads the principal the `BearerAuth` middleware attached to the request
+impl FromRequest for Principal {
+    type Config = ();
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(request: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ok(request
+            .extensions()
+            .get::<Principal>()
+            .cloned()
+            .unwrap_or_else(Principal::anonymous))
+    }
+}
+
+/// The configured tokens, with none every request is anonymous unless authentication is disabled
+#[derive(Default)]
+pub struct AccessTokens {
+    tokens: HashMap<String, Principal>,
+    disabled: bool,
+}
+
+impl AccessTokens {
+    /// Parses one `name token scope,scope` definition per line, lines starting with `#` are ignored
+    pub fn parse(definitions: &str) -> Result<AccessTokens, AccessTokenError> {
+        let mut tokens = HashMap::new();
+        for line in definitions.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<_> = line.split_whitespace().collect();
+            let (name, token, scopes) = match parts.as_slice() {
+                [name, token, scopes] => (name, token, scopes),
+                _ => return Err(AccessTokenError::InvalidDefinition(line.to_string())),
+            };
+            let scopes: Result<HashSet<_>, _> = scopes.split(',').map(str::parse).collect();
+            tokens.insert(
+                token.to_string(),
+                Principal {
+                    name: name.to_string(),
+                    scopes: scopes?,
+                },
+            );
+        }
+        Ok(AccessTokens {
+            tokens,
+            disabled: false,
+        })
+    }
+
+    /// Every request may use every scope, for servers only reachable by trusted clients
+    pub fn disabled() -> AccessTokens {
+        AccessTokens {
+            tokens: HashMap::new(),
+            disabled: true,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.disabled
+    }
+
+    pub fn extend(&mut self, other: AccessTokens) {
+        self.tokens.extend(other.tokens);
+    }
+
+    /// `None` means the request sent a token that isn't known
+    pub fn principal(&self, authorization: Option<&str>) -> Option<Principal> {
+        if self.disabled {
+            return Some(Principal::unrestricted());
+        }
+        match authorization {
+            None => Some(Principal::anonymous()),
+            Some(authorization) => {
+                let token = authorization.strip_prefix("Bearer ")?;
+                self.tokens.get(token.trim()).cloned()
+            }
+        }
+    }
+}
+
+/// Middleware attaching the request's `Principal`, or rejecting unknown tokens
+pub struct BearerAuth {
+    tokens: Arc<AccessTokens>,
+}
+
+impl BearerAuth {
+    pub fn new(tokens: Arc<AccessTokens>) -> BearerAuth {
+        BearerAuth { tokens }
+    }
+}
+
+impl<S, B> Transform<S> for BearerAuth
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = BearerAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(BearerAuthMiddleware {
+            service,
+            tokens: self.tokens.clone(),
+        })
+    }
+}
+
+pub struct BearerAuthMiddleware<S> {
+    service: S,
+    tokens: Arc<AccessTokens>,
+}
+
+impl<S, B> Service for BearerAuthMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, Ready<Result<Self::Response, Self::Error>>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: ServiceRequest) -> Self::Future {
+        let authorization = request
+            .headers()
+            .get(AUTHORIZATION)
+            .map(|value| value.to_str().unwrap_or_default());
+        match self.tokens.principal(authorization) {
+            Some(principal) => {
+                request.extensions_mut().insert(principal);
+                Either::Left(self.service.call(request))
+            }
+            None => Either::Right(err(ErrorUnauthorized("invalid access token"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> AccessTokens {
+        AccessTokens::parse(
+            "# name token scopes
+            website abc forecast
+            station def forecast,observations
+            ops ghi admin",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_principal() {
+        let tokens = tokens();
+        let website = tokens.principal(Some("Bearer abc")).unwrap();
+        assert_eq!(website.name, "website");
+        assert!(website.has_scope(Scope::Forecast));
+        assert!(!website.has_scope(Scope::Observations));
+
+        let station = tokens.principal(Some("Bearer def")).unwrap();
+        assert!(station.has_scope(Scope::Observations));
+        assert!(!station.has_scope(Scope::Admin));
+
+        let ops = tokens.principal(Some("Bearer ghi")).unwrap();
+        assert!(ops.has_scope(Scope::Observations));
+    }
+
+    #[test]
+    fn test_anonymous_and_unknown_tokens() {
+        let tokens = tokens();
+        let anonymous = tokens.principal(None).unwrap();
+        assert!(!anonymous.has_scope(Scope::Forecast));
+        assert!(tokens.principal(Some("Bearer xyz")).is_none());
+        assert!(tokens.principal(Some("abc")).is_none());
+    }
+
+    #[test]
+    fn test_no_tokens_allows_nothing() {
+        let tokens = AccessTokens::default();
+        assert!(!tokens.principal(None).unwrap().has_scope(Scope::Forecast));
+        assert!(tokens.principal(Some("Bearer abc")).is_none());
+    }
+
+    #[test]
+    fn test_disabled_allows_everything() {
+        let principal = AccessTokens::disabled().principal(None).unwrap();
+        assert!(principal.has_scope(Scope::Observations));
+    }
+
+    #[test]
+    fn test_invalid_definitions() {
+        assert!(matches!(
+            AccessTokens::parse("website abc"),
+            Err(AccessTokenError::InvalidDefinition(_))
+        ));
+        assert!(matches!(
+            AccessTokens::parse("website abc everything"),
+            Err(AccessTokenError::InvalidScope(_))
+        ));
+    }
+}
@@ -0,0 +1,59 @@
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Scope {
+    Forecast,
+    Observations,
+    Admin,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ScopeConversionError {
+    #[error("expected forecast, observations or admin, found {0}")]
+    InvalidScope(String),
+}
+
+impl FromStr for Scope {
+    type Err = ScopeConversionError;
+
+    fn from_str(scope: &str) -> Result<Self, Self::Err> {
+        match scope {
+            "forecast" => Ok(Scope::Forecast),
+            "observations" => Ok(Scope::Observations),
+            "admin" => Ok(Scope::Admin),
+            _ => Err(ScopeConversionError::InvalidScope(scope.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Scope::Forecast => "forecast",
+            Scope::Observations => "observations",
+            Scope::Admin => "admin",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_round_trip() {
+        for scope in &[Scope::Forecast, Scope::Observations, Scope::Admin] {
+            assert_eq!(Scope::from_str(&scope.to_string()).unwrap(), *scope);
+        }
+    }
+
+    #[test]
+    fn test_unknown_scope() {
+        assert_eq!(
+            Scope::from_str("everything").unwrap_err(),
+            ScopeConversionError::InvalidScope("everything".to_string())
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! A stand in for DataPoint serving the bundled fixtures, so the API can be run without a Met Office
+//! key or internet access
+//!
+//! Start it with `cargo run --bin fake_datapoint`, then run the server with
+//! `MET_OFFICE_BASE_URL=http://127.0.0.1:8081/public/data MET_OFFICE_API_KEYS=fake AUTH_DISABLED=true`,
+//! adding `POLLEN_FORECAST_URL=http://127.0.0.1:8081/pollen-forecast` to fake the pollen page too.
+use std::collections::HashMap;
+use std::env;
+use std::io;
+
+use actix_web::{middleware, web, App, HttpRequest, HttpResponse, HttpServer};
+use chrono::{Duration, NaiveDate, Utc};
+
+/// Where the fake listens, `127.0.0.1:8081` by default
+const ADDR_VAR: &str = "FAKE_DATAPOINT_ADDR";
+const DEFAULT_ADDR: &str = "127.0.0.1:8081";
+/// The same prefix as DataPoint's URLs, so only the host changes in `MET_OFFICE_BASE_URL`
+const PATH_PREFIX: &str = "/public/data/";
+/// Stands in for the Met Office website's pollen forecast page, which needs no key
+const POLLEN_PATH: &str = "/pollen-forecast";
+
+/// The fixtures were recorded on this day, which is moved to today along with the days around it
+const RECORDED_PREVIOUS_DAY: &str = "2020-10-16";
+const RECORDED_DAY: &str = "2020-10-17";
+const RECORDED_NEXT_DAY: &str = "2020-10-18";
+
+const FORECAST_SITE_LIST: &str = include_str!("../../fixtures/forecast_sitelist.json");
+const OBSERVATION_SITE_LIST: &str = include_str!("../../fixtures/observation_sitelist.json");
+const CAPABILITIES: &str = include_str!("../../fixtures/capabilities.json");
+const FORECAST: &str = include_str!("../../fixtures/forecast.json");
+const FORECAST_ALL: &str = include_str!("../../fixtures/forecast_all.json");
+const DAILY_FORECAST: &str = include_str!("../../fixtures/daily_forecast.json");
+const OBSERVATIONS: &str = include_str!("../../fixtures/observations.json");
+const REGIONAL_FORECAST_SITE_LIST: &str =
+    include_str!("../../fixtures/regional_forecast_sitelist.json");
+const REGIONAL_FORECAST: &str = include_str!("../../fixtures/regional_forecast.json");
+const NATIONAL_PARK_SITE_LIST: &str = include_str!("../../fixtures/national_park_sitelist.json");
+const NATIONAL_PARK_FORECAST: &str = include_str!("../../fixtures/national_park_forecast.json");
+const MOUNTAIN_AREA_SITE_LIST: &str = include_str!("../../fixtures/mountain_area_sitelist.json");
+const MOUNTAIN_AREA_FORECAST: &str = include_str!("../../fixtures/mountain_area_forecast.json");
+const UK_EXTREMES: &str = include_str!("../../fixtures/uk_extremes.json");
+const FORECAST_LAYERS: &str = include_str!("../../fixtures/forecast_layers.json");
+const OBSERVATION_LAYERS: &str = include_str!("../../fixtures/observation_layers.json");
+const SURFACE_PRESSURE_CAPABILITIES: &str =
+    include_str!("../../fixtures/surface_pressure_capabilities.json");
+const POLLEN_FORECAST: &str = include_str!("../../fixtures/pollen_forecast.html");
+
+/// Site forecasts and observations are the one recorded site's, relabelled with the requested id
+fn for_site(fixture: &str, recorded_id: &str, site_id: &str) -> Option<String> {
+    if site_id.parse::<u32>().is_err() {
+        return None;
+    }
+    Some(fixture.replace(
+        &format!("\"i\": \"{}\"", recorded_id),
+        &format!("\"i\": \"{}\"", site_id),
+    ))
+}
+
+/// The response for a path below `PATH_PREFIX`, `None` for anything DataPoint would 404
+fn response_body(path: &str, res: Option<&str>) -> Option<String> {
+    let parts: Vec<_> = path.split('/').collect();
+    match (parts.as_slice(), res) {
+        (["val", "wxfcs", "all", "json", "sitelist"], _) => Some(FORECAST_SITE_LIST.to_string()),
+        (["val", "wxfcs", "all", "json", "capabilities"], Some("3hourly")) => {
+            Some(CAPABILITIES.to_string())
+        }
+        (["val", "wxfcs", "all", "json", "all"], Some("3hourly")) => Some(FORECAST_ALL.to_string()),
+        (["val", "wxfcs", "all", "json", site], Some("3hourly")) => {
+            for_site(FORECAST, "310069", site)
+        }
+        (["val", "wxfcs", "all", "json", site], Some("daily")) => {
+            for_site(DAILY_FORECAST, "310069", site)
+        }
+        (["val", "wxobs", "all", "json", "sitelist"], _) => Some(OBSERVATION_SITE_LIST.to_string()),
+        (["val", "wxobs", "all", "json", site], Some("hourly")) => {
+            for_site(OBSERVATIONS, "3840", site)
+        }
+        (["txt", "wxfcs", "regionalforecast", "json", "sitelist"], _) => {
+            Some(REGIONAL_FORECAST_SITE_LIST.to_string())
+        }
+        (["txt", "wxfcs", "regionalforecast", "json", _], _) => Some(REGIONAL_FORECAST.to_string()),
+        (["txt", "wxfcs", "nationalpark", "json", "sitelist"], _) => {
+            Some(NATIONAL_PARK_SITE_LIST.to_string())
+        }
+        (["txt", "wxfcs", "nationalpark", "json", _], _) => {
+            Some(NATIONAL_PARK_FORECAST.to_string())
+        }
+        (["txt", "wxfcs", "mountainarea", "json", "sitelist"], _) => {
+            Some(MOUNTAIN_AREA_SITE_LIST.to_string())
+        }
+        (["txt", "wxfcs", "mountainarea", "json", _], _) => {
+            Some(MOUNTAIN_AREA_FORECAST.to_string())
+        }
+        (["txt", "wxobs", "ukextremes", "json", "latest"], _) => Some(UK_EXTREMES.to_string()),
+        (["layer", "wxfcs", "all", "json", "capabilities"], _) => Some(FORECAST_LAYERS.to_string()),
+        (["layer", "wxobs", "all", "json", "capabilities"], _) => {
+            Some(OBSERVATION_LAYERS.to_string())
+        }
+        (["image", "wxfcs", "surfacepressure", "json", "capabilities"], _) => {
+            Some(SURFACE_PRESSURE_CAPABILITIES.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Moves the recorded data to `today`, so forecasts look current to clients
+fn move_to(body: &str, today: NaiveDate) -> String {
+    let day = |offset| {
+        (today + Duration::days(offset))
+            .format("%Y-%m-%d")
+            .to_string()
+    };
+    body.replace(RECORDED_PREVIOUS_DAY, &day(-1))
+        .replace(RECORDED_DAY, &day(0))
+        .replace(RECORDED_NEXT_DAY, &day(1))
+}
+
+/// Like DataPoint, any request without a `key` is refused
+async fn datapoint(
+    request: HttpRequest,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    if query.get("key").filter(|key| !key.is_empty()).is_none() {
+        return HttpResponse::Forbidden()
+            .content_type("text/html")
+            .body("<html><body>Forbidden</body></html>");
+    }
+    let path = request.path().strip_prefix(PATH_PREFIX).unwrap_or_default();
+    match response_body(path, query.get("res").map(String::as_str)) {
+        Some(body) => HttpResponse::Ok()
+            .content_type("application/json")
+            .body(move_to(&body, Utc::today().naive_utc())),
+        None => HttpResponse::NotFound()
+            .content_type("text/html")
+            .body("<html><body>Not Found</body></html>"),
+    }
+}
+
+async fn pollen_forecast() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(POLLEN_FORECAST)
+}
+
+#[actix_web::main]
+async fn main() -> io::Result<()> {
+    std::env::set_var("RUST_LOG", "actix_web=info");
+    env_logger::init();
+
+    let addr = env::var(ADDR_VAR).unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+    HttpServer::new(|| {
+        App::new()
+            .wrap(middleware::Logger::default())
+            .service(web::resource(POLLEN_PATH).route(web::get().to(pollen_forecast)))
+            .default_service(web::get().to(datapoint))
+    })
+    .bind(addr)?
+    .run()
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_forecasts_are_relabelled() {
+        let body = response_body("val/wxfcs/all/json/3840", Some("3hourly")).unwrap();
+        assert!(body.contains("\"i\": \"3840\""));
+        assert!(response_body("val/wxfcs/all/json/3840", None).is_none());
+        assert!(response_body("val/wxfcs/all/json/exeter", Some("3hourly")).is_none());
+        assert!(response_body("val/wxobs/all/json/14", Some("hourly"))
+            .unwrap()
+            .contains("\"i\": \"14\""));
+    }
+
+    #[test]
+    fn test_fixtures_parse() {
+        for path in &[
+            "val/wxfcs/all/json/sitelist",
+            "val/wxobs/all/json/sitelist",
+            "txt/wxfcs/regionalforecast/json/sitelist",
+            "txt/wxobs/ukextremes/json/latest",
+            "image/wxfcs/surfacepressure/json/capabilities",
+        ] {
+            let body = response_body(path, None).unwrap();
+            assert!(serde_json::from_str::<serde_json::Value>(&body).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_observation_site_list() {
+        let body = response_body("val/wxobs/all/json/sitelist", None).unwrap();
+        assert!(body.contains("\"id\": \"3840\""));
+        assert_ne!(
+            body,
+            response_body("val/wxfcs/all/json/sitelist", None).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_move_to() {
+        let today = NaiveDate::from_ymd(2026, 3, 31);
+        assert_eq!(
+            move_to(
+                r#"["2020-10-16", "2020-10-17T15:00:00Z", "2020-10-18Z"]"#,
+                today
+            ),
+            r#"["2026-03-30", "2026-03-31T15:00:00Z", "2026-04-01Z"]"#
+        );
+    }
+}
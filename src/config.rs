@@ -0,0 +1,471 @@
+//! Server configuration, read from environment variables
+use std::str::FromStr;
+use std::time::Duration;
+use std::{env, fs, io};
+
+use crate::auth::AccessTokens;
+use crate::met_office::{ApiKey, LocationId, MetApiConfig, RateLimit};
+
+/// Overrides the DataPoint base URL, for example to run against a local fake
+const BASE_URL_VAR: &str = "MET_OFFICE_BASE_URL";
+/// Overrides the Met Office website page the pollen forecast is scraped from
+const POLLEN_URL_VAR: &str = "POLLEN_FORECAST_URL";
+/// Timeouts for DataPoint requests in seconds, see `MetApiConfig`
+const TIMEOUT_VAR: &str = "MET_OFFICE_TIMEOUT_SECS";
+const CONNECT_TIMEOUT_VAR: &str = "MET_OFFICE_CONNECT_TIMEOUT_SECS";
+/// Retries of DataPoint requests that fail with a network or server error, see `RetryConfig`
+const MAX_RETRIES_VAR: &str = "MET_OFFICE_MAX_RETRIES";
+const RETRY_BACKOFF_VAR: &str = "MET_OFFICE_RETRY_BACKOFF_MS";
+/// When DataPoint requests are paused after failing, see `CircuitBreakerConfig`
+const CIRCUIT_BREAKER_FAILURES_VAR: &str = "CIRCUIT_BREAKER_FAILURES";
+const CIRCUIT_BREAKER_COOLDOWN_VAR: &str = "CIRCUIT_BREAKER_COOLDOWN_SECS";
+/// Comma separated Met Office API keys, each optionally followed by its request limits as
+/// `key:per_minute:per_day`
+const API_KEYS_VAR: &str = "MET_OFFICE_API_KEYS";
+/// A file with one Met Office API key per line, so keys don't have to be in the environment
+const API_KEY_FILE_VAR: &str = "MET_OFFICE_API_KEY_FILE";
+/// Limits on the DataPoint response cache, see `ResponseCacheConfig`
+const CACHE_MAX_ENTRIES_VAR: &str = "RESPONSE_CACHE_MAX_ENTRIES";
+const CACHE_MAX_BYTES_VAR: &str = "RESPONSE_CACHE_MAX_BYTES";
+/// How long in seconds an expired response may be served while DataPoint is unavailable
+const CACHE_MAX_STALE_VAR: &str = "RESPONSE_CACHE_MAX_STALE_SECS";
+/// Keeps cached responses in this directory between restarts, needs the `disk-cache` feature
+#[cfg(feature = "disk-cache")]
+const CACHE_DIR_VAR: &str = "RESPONSE_CACHE_DIR";
+/// Comma separated sites whose forecasts and observations are fetched after each DataPoint update
+const PREFETCH_LOCATIONS_VAR: &str = "PREFETCH_LOCATIONS";
+/// How long in seconds after the hourly DataPoint update the watched sites are fetched
+const PREFETCH_DELAY_VAR: &str = "PREFETCH_DELAY_SECS";
+/// DataPoint has usually published the hour's data ten minutes after it
+const DEFAULT_PREFETCH_DELAY: Duration = Duration::from_secs(600);
+/// The delay has to be less than the hour between DataPoint updates
+const MAX_PREFETCH_DELAY: Duration = Duration::from_secs(60 * 60);
+/// Access token definitions separated by `;`, see `AccessTokens::parse`
+const ACCESS_TOKENS_VAR: &str = "ACCESS_TOKENS";
+/// A file with one access token definition per line
+const ACCESS_TOKEN_FILE_VAR: &str = "ACCESS_TOKEN_FILE";
+/// `true` lets every request use every scope, the server won't start without tokens otherwise
+const AUTH_DISABLED_VAR: &str = "AUTH_DISABLED";
+
+pub struct Config {
+    pub met_api: MetApiConfig,
+    pub api_keys: Vec<ApiKey>,
+    pub access_tokens: AccessTokens,
+    pub watched_locations: Vec<LocationId>,
+    pub prefetch_delay: Duration,
+}
+
+fn parse_access_tokens(definitions: &str) -> io::Result<AccessTokens> {
+    AccessTokens::parse(definitions)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+fn parse_number<T: FromStr>(name: &str, value: &str) -> io::Result<T> {
+    value.trim().parse().map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} should be a number, found {}", name, value),
+        )
+    })
+}
+
+fn parse_locations(locations: &str) -> io::Result<Vec<LocationId>> {
+    locations
+        .split(',')
+        .map(str::trim)
+        .filter(|location| !location.is_empty())
+        .map(|location| {
+            location
+                .parse()
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+        })
+        .collect()
+}
+
+fn parse_api_key(definition: &str) -> io::Result<ApiKey> {
+    let parts: Vec<_> = definition.split(':').collect();
+    match parts.as_slice() {
+        [key] => Ok(ApiKey::new(key)),
+        [key, per_minute, per_day] => Ok(ApiKey {
+            key: key.to_string(),
+            rate_limit: RateLimit {
+                per_minute: parse_number(API_KEYS_VAR, per_minute)?,
+                per_day: parse_number(API_KEYS_VAR, per_day)?,
+            },
+        }),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "expected an API key as key or key:per_minute:per_day",
+        )),
+    }
+}
+
+fn parse_api_keys(keys: &str) -> io::Result<Vec<ApiKey>> {
+    keys.split(&[',', '\n'][..])
+        .map(str::trim)
+        .filter(|key| !key.is_empty() && !key.starts_with('#'))
+        .map(parse_api_key)
+        .collect()
+}
+
+impl Config {
+    pub fn from_env() -> io::Result<Config> {
+        Config::from_vars(|name| env::var(name).ok(), fs::read_to_string)
+    }
+
+    fn from_vars<F, R>(var: F, read_file: R) -> io::Result<Config>
+    where
+        F: Fn(&str) -> Option<String>,
+        R: Fn(String) -> io::Result<String>,
+    {
+        let mut api_keys = match var(API_KEYS_VAR) {
+            Some(keys) => parse_api_keys(&keys)?,
+            None => Vec::new(),
+        };
+        if let Some(path) = var(API_KEY_FILE_VAR) {
+            api_keys.extend(parse_api_keys(&read_file(path)?)?);
+        }
+        let mut access_tokens = match var(ACCESS_TOKENS_VAR) {
+            Some(definitions) => parse_access_tokens(&definitions.replace(';', "\n"))?,
+            None => AccessTokens::default(),
+        };
+        if let Some(path) = var(ACCESS_TOKEN_FILE_VAR) {
+            access_tokens.extend(parse_access_tokens(&read_file(path)?)?);
+        }
+        match var(AUTH_DISABLED_VAR).as_deref().map(str::trim) {
+            None | Some("false") => {}
+            Some("true") if access_tokens.is_empty() => access_tokens = AccessTokens::disabled(),
+            Some("true") => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} is set but access tokens are configured",
+                        AUTH_DISABLED_VAR
+                    ),
+                ))
+            }
+            Some(value) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{} should be true or false, found {}",
+                        AUTH_DISABLED_VAR, value
+                    ),
+                ))
+            }
+        }
+        let mut met_api = var(BASE_URL_VAR)
+            .map(|base_url| MetApiConfig::new(&base_url))
+            .unwrap_or_default();
+        if let Some(pollen_url) = var(POLLEN_URL_VAR) {
+            met_api.pollen_url = pollen_url;
+        }
+        if let Some(timeout) = var(TIMEOUT_VAR) {
+            met_api.timeout = Duration::from_secs(parse_number(TIMEOUT_VAR, &timeout)?);
+        }
+        if let Some(connect_timeout) = var(CONNECT_TIMEOUT_VAR) {
+            met_api.connect_timeout =
+                Duration::from_secs(parse_number(CONNECT_TIMEOUT_VAR, &connect_timeout)?);
+        }
+        if let Some(max_retries) = var(MAX_RETRIES_VAR) {
+            met_api.retry.max_retries = parse_number(MAX_RETRIES_VAR, &max_retries)?;
+        }
+        if let Some(backoff) = var(RETRY_BACKOFF_VAR) {
+            met_api.retry.initial_backoff =
+                Duration::from_millis(parse_number(RETRY_BACKOFF_VAR, &backoff)?);
+        }
+        if let Some(failures) = var(CIRCUIT_BREAKER_FAILURES_VAR) {
+            met_api.circuit_breaker.failure_threshold =
+                parse_number(CIRCUIT_BREAKER_FAILURES_VAR, &failures)?;
+        }
+        if let Some(cooldown) = var(CIRCUIT_BREAKER_COOLDOWN_VAR) {
+            met_api.circuit_breaker.cooldown =
+                Duration::from_secs(parse_number(CIRCUIT_BREAKER_COOLDOWN_VAR, &cooldown)?);
+        }
+        if let Some(max_entries) = var(CACHE_MAX_ENTRIES_VAR) {
+            met_api.cache.max_entries = parse_number(CACHE_MAX_ENTRIES_VAR, &max_entries)?;
+        }
+        if let Some(max_bytes) = var(CACHE_MAX_BYTES_VAR) {
+            met_api.cache.max_bytes = parse_number(CACHE_MAX_BYTES_VAR, &max_bytes)?;
+        }
+        if let Some(max_stale) = var(CACHE_MAX_STALE_VAR) {
+            met_api.cache.max_stale =
+                Duration::from_secs(parse_number(CACHE_MAX_STALE_VAR, &max_stale)?);
+        }
+        let watched_locations = match var(PREFETCH_LOCATIONS_VAR) {
+            Some(locations) => parse_locations(&locations)?,
+            None => Vec::new(),
+        };
+        let prefetch_delay = match var(PREFETCH_DELAY_VAR) {
+            Some(delay) => Duration::from_secs(parse_number(PREFETCH_DELAY_VAR, &delay)?),
+            None => DEFAULT_PREFETCH_DELAY,
+        };
+        if prefetch_delay >= MAX_PREFETCH_DELAY {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} should be less than {}, DataPoint updates every hour",
+                    PREFETCH_DELAY_VAR,
+                    MAX_PREFETCH_DELAY.as_secs()
+                ),
+            ));
+        }
+        #[cfg(feature = "disk-cache")]
+        {
+            met_api.cache.disk_dir = var(CACHE_DIR_VAR).map(Into::into);
+        }
+        Ok(Config {
+            met_api,
+            api_keys,
+            access_tokens,
+            watched_locations,
+            prefetch_delay,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_file(_: String) -> io::Result<String> {
+        Err(io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = Config::from_vars(|_| None, no_file).unwrap();
+        assert!(config.met_api.base_url.starts_with("https://"));
+        assert!(config.api_keys.is_empty());
+        assert!(config.access_tokens.is_empty());
+        assert!(!config.access_tokens.is_disabled());
+        assert!(config.watched_locations.is_empty());
+        assert_eq!(config.prefetch_delay, DEFAULT_PREFETCH_DELAY);
+    }
+
+    #[test]
+    fn test_base_url_override() {
+        let config = Config::from_vars(
+            |name| match name {
+                BASE_URL_VAR => Some("http://localhost:9000/public/data/".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .unwrap();
+        assert_eq!(config.met_api.base_url, "http://localhost:9000/public/data");
+    }
+
+    #[test]
+    fn test_pollen_url_override() {
+        let config = Config::from_vars(|_| None, no_file).unwrap();
+        assert!(config
+            .met_api
+            .pollen_url
+            .starts_with("https://www.metoffice.gov.uk/"));
+        let config = Config::from_vars(
+            |name| match name {
+                POLLEN_URL_VAR => Some("http://localhost:9000/pollen".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .unwrap();
+        assert_eq!(config.met_api.pollen_url, "http://localhost:9000/pollen");
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let config = Config::from_vars(
+            |name| match name {
+                TIMEOUT_VAR => Some("30".to_string()),
+                CONNECT_TIMEOUT_VAR => Some("2".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .unwrap();
+        assert_eq!(config.met_api.timeout, Duration::from_secs(30));
+        assert_eq!(config.met_api.connect_timeout, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_retries_and_circuit_breaker() {
+        let config = Config::from_vars(
+            |name| match name {
+                MAX_RETRIES_VAR => Some("0".to_string()),
+                RETRY_BACKOFF_VAR => Some("50".to_string()),
+                CIRCUIT_BREAKER_FAILURES_VAR => Some("3".to_string()),
+                CIRCUIT_BREAKER_COOLDOWN_VAR => Some("60".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .unwrap();
+        assert_eq!(config.met_api.retry.max_retries, 0);
+        assert_eq!(
+            config.met_api.retry.initial_backoff,
+            Duration::from_millis(50)
+        );
+        assert_eq!(config.met_api.circuit_breaker.failure_threshold, 3);
+        assert_eq!(
+            config.met_api.circuit_breaker.cooldown,
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_cache_limits() {
+        let config = Config::from_vars(
+            |name| match name {
+                CACHE_MAX_ENTRIES_VAR => Some("10".to_string()),
+                CACHE_MAX_BYTES_VAR => Some("1024".to_string()),
+                CACHE_MAX_STALE_VAR => Some("600".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .unwrap();
+        assert_eq!(config.met_api.cache.max_entries, 10);
+        assert_eq!(config.met_api.cache.max_bytes, 1024);
+        assert_eq!(config.met_api.cache.max_stale, Duration::from_secs(600));
+        assert!(Config::from_vars(
+            |name| match name {
+                CACHE_MAX_ENTRIES_VAR => Some("lots".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_prefetch() {
+        let config = Config::from_vars(
+            |name| match name {
+                PREFETCH_LOCATIONS_VAR => Some("3840, 310069".to_string()),
+                PREFETCH_DELAY_VAR => Some("300".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .unwrap();
+        assert_eq!(
+            config.watched_locations,
+            vec![LocationId::Location(3840), LocationId::Location(310069)]
+        );
+        assert_eq!(config.prefetch_delay, Duration::from_secs(300));
+        assert!(Config::from_vars(
+            |name| match name {
+                PREFETCH_LOCATIONS_VAR => Some("3840,exeter".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .is_err());
+        assert!(Config::from_vars(
+            |name| match name {
+                PREFETCH_DELAY_VAR => Some("3600".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_api_keys() {
+        let config = Config::from_vars(
+            |name| match name {
+                API_KEYS_VAR => Some("a, b".to_string()),
+                API_KEY_FILE_VAR => Some("keys.txt".to_string()),
+                _ => None,
+            },
+            |path| {
+                assert_eq!(path, "keys.txt");
+                Ok("# spare keys\nc:10:500\n\nd\n".to_string())
+            },
+        )
+        .unwrap();
+        let keys: Vec<_> = config.api_keys.iter().map(|key| key.key.as_str()).collect();
+        assert_eq!(keys, vec!["a", "b", "c", "d"]);
+        assert_eq!(config.api_keys[0].rate_limit, RateLimit::default());
+        assert_eq!(
+            config.api_keys[2].rate_limit,
+            RateLimit {
+                per_minute: 10,
+                per_day: 500
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_key_file() {
+        let config = Config::from_vars(
+            |name| match name {
+                API_KEY_FILE_VAR => Some("missing.txt".to_string()),
+                _ => None,
+            },
+            no_file,
+        );
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_access_tokens() {
+        let config = Config::from_vars(
+            |name| match name {
+                ACCESS_TOKENS_VAR => Some("website abc forecast;ops def admin".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .unwrap();
+        assert_eq!(
+            config
+                .access_tokens
+                .principal(Some("Bearer def"))
+                .unwrap()
+                .name,
+            "ops"
+        );
+        assert!(Config::from_vars(
+            |name| match name {
+                ACCESS_TOKENS_VAR => Some("website abc everything".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_auth_disabled() {
+        let config = Config::from_vars(
+            |name| match name {
+                AUTH_DISABLED_VAR => Some("true".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .unwrap();
+        assert!(config.access_tokens.is_disabled());
+        assert!(Config::from_vars(
+            |name| match name {
+                AUTH_DISABLED_VAR => Some("true".to_string()),
+                ACCESS_TOKENS_VAR => Some("website abc forecast".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .is_err());
+        assert!(Config::from_vars(
+            |name| match name {
+                AUTH_DISABLED_VAR => Some("yes".to_string()),
+                _ => None,
+            },
+            no_file,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,21 @@
+//! A health check for load balancers and monitoring, with the state of the DataPoint circuit breaker
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+
+use crate::met_office::{CircuitState, CircuitStatus, MetApi};
+
+#[derive(Serialize)]
+struct Health {
+    status: &'static str,
+    upstream: CircuitStatus,
+}
+
+/// Answers 200 while the server is up, `status` is `degraded` while DataPoint requests are paused
+pub async fn health(met_api: web::Data<MetApi>) -> HttpResponse {
+    let upstream = met_api.circuit_status();
+    let status = match upstream.state {
+        CircuitState::Closed => "ok",
+        CircuitState::Open | CircuitState::HalfOpen => "degraded",
+    };
+    HttpResponse::Ok().json(Health { status, upstream })
+}
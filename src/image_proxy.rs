@@ -0,0 +1,278 @@
+//! Serves DataPoint map layers and charts through this server
+//!
+//! The server's API keys are used, or one sent in the `X-Api-Key` header rather than the URL,
+//! and images are cached in memory so repeated views don't use up the DataPoint quota.
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use log::error;
+use serde::Deserialize;
+
+use crate::auth::{Principal, Scope};
+use crate::met_office::{is_valid_layer_name, Image, LayerRequest, MetApi, MetApiError};
+
+const API_KEY_HEADER: &str = "X-Api-Key";
+
+/// Charts are replaced upstream when a new run is published, so only keep them for a while
+const CHART_TTL: Duration = Duration::from_secs(30 * 60);
+/// How long browsers may keep images that never change upstream, a year
+const IMMUTABLE_MAX_AGE: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// A bounded cache of images, the oldest image is dropped once the capacity is reached
+pub struct ImageCache {
+    capacity: usize,
+    images: Mutex<CachedImages>,
+}
+
+struct CachedImage {
+    image: Arc<Image>,
+    expires: Option<Instant>,
+}
+
+impl CachedImage {
+    fn is_fresh(&self) -> bool {
+        match self.expires {
+            Some(expires) => expires > Instant::now(),
+            None => true,
+        }
+    }
+
+    /// How much longer the image is kept, `None` if it never expires
+    fn ttl(&self) -> Option<Duration> {
+        self.expires
+            .map(|expires| expires.saturating_duration_since(Instant::now()))
+    }
+}
+
+#[derive(Default)]
+struct CachedImages {
+    images: HashMap<String, CachedImage>,
+    order: VecDeque<String>,
+}
+
+impl ImageCache {
+    pub fn new(capacity: usize) -> ImageCache {
+        ImageCache {
+            capacity,
+            images: Mutex::new(CachedImages::default()),
+        }
+    }
+
+    /// The image and how much longer it is kept, `None` if it never expires
+    pub fn get(&self, key: &str) -> Option<(Arc<Image>, Option<Duration>)> {
+        let cached = self.images.lock().unwrap();
+        cached
+            .images
+            .get(key)
+            .filter(|cached| cached.is_fresh())
+            .map(|cached| (cached.image.clone(), cached.ttl()))
+    }
+
+    /// Images without a time to live never change upstream, such as a layer for a given time
+    pub fn insert(&self, key: String, image: Image, ttl: Option<Duration>) -> Arc<Image> {
+        let image = Arc::new(image);
+        let entry = CachedImage {
+            image: image.clone(),
+            expires: ttl.map(|ttl| Instant::now() + ttl),
+        };
+        let mut cached = self.images.lock().unwrap();
+        if cached.images.insert(key.clone(), entry).is_none() {
+            cached.order.push_back(key);
+        }
+        while cached.order.len() > self.capacity {
+            if let Some(oldest) = cached.order.pop_front() {
+                cached.images.remove(&oldest);
+            }
+        }
+        image
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LayerQuery {
+    #[serde(rename = "RUN")]
+    run: Option<String>,
+    #[serde(rename = "FORECAST")]
+    forecast: Option<u32>,
+    #[serde(rename = "TIME")]
+    time: Option<String>,
+}
+
+/// Uses the client's key when it sends one, otherwise the server's keys
+fn request_met_api(met_api: &MetApi, request: &HttpRequest) -> MetApi {
+    match request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(api_key) => met_api.with_api_key(api_key.to_string()),
+        None => met_api.clone(),
+    }
+}
+
+/// Browsers and proxies keep an image no longer than this server does
+fn cache_control(ttl: Option<Duration>) -> String {
+    match ttl {
+        Some(ttl) => format!("public, max-age={}", ttl.as_secs()),
+        None => format!("public, max-age={}, immutable", IMMUTABLE_MAX_AGE.as_secs()),
+    }
+}
+
+fn image_response(image: &Image, ttl: Option<Duration>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(image.content_type.as_str())
+        .header("Cache-Control", cache_control(ttl))
+        .body(image.data.clone())
+}
+
+async fn serve_cached<F>(
+    cache: &ImageCache,
+    key: String,
+    ttl: Option<Duration>,
+    fetch: F,
+) -> HttpResponse
+where
+    F: Future<Output = Result<Image, MetApiError>>,
+{
+    if let Some((image, remaining)) = cache.get(&key) {
+        return image_response(&image, remaining);
+    }
+    match fetch.await {
+        Ok(image) => image_response(&cache.insert(key, image, ttl), ttl),
+        Err(error) => {
+            error!("could not fetch {}: {}", key, error);
+            match error {
+                MetApiError::MissingApiKey => HttpResponse::Unauthorized().finish(),
+                MetApiError::InvalidApiKey(_) => HttpResponse::Forbidden().finish(),
+                MetApiError::RateLimited(_) | MetApiError::QuotaExhausted => {
+                    HttpResponse::TooManyRequests().finish()
+                }
+                MetApiError::NotFound(_) => HttpResponse::NotFound().finish(),
+                MetApiError::CircuitOpen => HttpResponse::ServiceUnavailable().finish(),
+                _ => HttpResponse::BadGateway().finish(),
+            }
+        }
+    }
+}
+
+pub async fn layer_image(
+    cache: web::Data<Arc<ImageCache>>,
+    met_api: web::Data<MetApi>,
+    principal: Principal,
+    path: web::Path<(String, String, String)>,
+    query: web::Query<LayerQuery>,
+    request: HttpRequest,
+) -> HttpResponse {
+    let (kind, layer_name, image_format) = path.into_inner();
+    let layer_request = match LayerRequest::from_parts(
+        &kind,
+        query.run.as_deref(),
+        query.forecast,
+        query.time.as_deref(),
+    ) {
+        Some(layer_request)
+            if is_valid_layer_name(&layer_name) && is_valid_layer_name(&image_format) =>
+        {
+            layer_request
+        }
+        _ => return HttpResponse::BadRequest().finish(),
+    };
+    let scope = match layer_request {
+        LayerRequest::Forecast { .. } => Scope::Forecast,
+        LayerRequest::Observation { .. } => Scope::Observations,
+    };
+    if !principal.has_scope(scope) {
+        return HttpResponse::Forbidden().finish();
+    }
+    let met_api = request_met_api(&met_api, &request);
+    let key = format!(
+        "layer/{}/{}/{}?{}",
+        kind,
+        layer_name,
+        image_format,
+        layer_request.query()
+    );
+    serve_cached(
+        &cache,
+        key,
+        None,
+        met_api.layer_image(&layer_name, &image_format, &layer_request),
+    )
+    .await
+}
+
+pub async fn surface_pressure_chart(
+    cache: web::Data<Arc<ImageCache>>,
+    met_api: web::Data<MetApi>,
+    principal: Principal,
+    forecast_period: web::Path<u32>,
+    request: HttpRequest,
+) -> HttpResponse {
+    if !principal.has_scope(Scope::Forecast) {
+        return HttpResponse::Forbidden().finish();
+    }
+    let forecast_period = forecast_period.into_inner();
+    let met_api = request_met_api(&met_api, &request);
+    let key = format!("surfacepressure/{}", forecast_period);
+    serve_cached(
+        &cache,
+        key,
+        Some(CHART_TTL),
+        met_api.surface_pressure_chart(forecast_period),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(data: u8) -> Image {
+        Image {
+            content_type: "image/png".to_string(),
+            data: vec![data],
+        }
+    }
+
+    #[test]
+    fn test_cache_hit() {
+        let cache = ImageCache::new(2);
+        assert!(cache.get("a").is_none());
+        cache.insert("a".to_string(), image(1), None);
+        assert_eq!(cache.get("a").unwrap().0.data, vec![1]);
+    }
+
+    #[test]
+    fn test_cache_expiry() {
+        let cache = ImageCache::new(2);
+        cache.insert("a".to_string(), image(1), Some(Duration::from_secs(0)));
+        cache.insert("b".to_string(), image(2), Some(Duration::from_secs(60)));
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.get("b").unwrap().0.data, vec![2]);
+    }
+
+    #[test]
+    fn test_cache_control_follows_the_ttl() {
+        let cache = ImageCache::new(2);
+        cache.insert("chart".to_string(), image(1), Some(CHART_TTL));
+        let (_, remaining) = cache.get("chart").unwrap();
+        assert!(remaining.unwrap() <= CHART_TTL);
+        assert_eq!(cache_control(Some(CHART_TTL)), "public, max-age=1800");
+        assert_eq!(cache_control(None), "public, max-age=31536000, immutable");
+    }
+
+    #[test]
+    fn test_cache_drops_oldest() {
+        let cache = ImageCache::new(2);
+        cache.insert("a".to_string(), image(1), None);
+        cache.insert("b".to_string(), image(2), None);
+        cache.insert("a".to_string(), image(3), None);
+        cache.insert("c".to_string(), image(4), None);
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.get("b").unwrap().0.data, vec![2]);
+        assert_eq!(cache.get("c").unwrap().0.data, vec![4]);
+    }
+}
@@ -3,19 +3,38 @@
 //! A simple example integrating juniper in actix-web
 use std::io;
 use std::sync::Arc;
+use std::time::Duration;
 
 use actix_cors::Cors;
 use actix_web::{middleware, web, App, Error, HttpResponse, HttpServer};
 use juniper::http::graphiql::graphiql_source;
 use juniper::http::GraphQLRequest;
+use log::warn;
 
+mod api_error;
+mod auth;
+mod config;
+mod health;
+mod image_proxy;
 mod met_office;
+mod pollen;
+mod prefetch;
 mod schema;
 
-use crate::schema::{create_schema, Schema};
+use crate::auth::{BearerAuth, Principal};
+use crate::config::Config;
+use crate::image_proxy::ImageCache;
+use crate::met_office::{MetApi, CHART_PROXY_PATH, LAYER_PROXY_PATH};
+use crate::pollen::PollenApi;
+use crate::prefetch::WatchList;
+use crate::schema::{create_schema, Context, Schema};
+
+const IMAGE_CACHE_CAPACITY: usize = 1000;
+/// How often responses served stale are refetched while DataPoint is unavailable
+const REVALIDATE_INTERVAL: Duration = Duration::from_secs(60);
 
 async fn graphiql() -> HttpResponse {
-    let html = graphiql_source("http://127.0.0.1:8080/");
+    let html = graphiql_source("http://127.0.0.1:8080/", None);
     HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
         .body(html)
@@ -23,30 +42,73 @@ async fn graphiql() -> HttpResponse {
 
 async fn graphql(
     st: web::Data<Arc<Schema>>,
+    met_api: web::Data<MetApi>,
+    watch_list: web::Data<Arc<WatchList>>,
+    pollen: web::Data<Arc<PollenApi>>,
+    principal: Principal,
     data: web::Json<GraphQLRequest>,
 ) -> Result<HttpResponse, Error> {
-    let user = web::block(move || {
-        let res = data.execute(&st, &());
-        Ok::<_, serde_json::error::Error>(serde_json::to_string(&res)?)
-    })
-    .await?;
-    Ok(HttpResponse::Ok()
-        .content_type("application/json")
-        .body(user))
+    let context = Context {
+        met_api: met_api.get_ref().clone(),
+        principal,
+        watch_list: watch_list.get_ref().clone(),
+        pollen: pollen.get_ref().clone(),
+    };
+    let res = data.execute(&st, &context).await;
+    Ok(HttpResponse::Ok().json(res))
 }
 
 #[actix_web::main]
 async fn main() -> io::Result<()> {
-    std::env::set_var("RUST_LOG", "actix_web=info");
+    std::env::set_var("RUST_LOG", "actix_web=info,weather_api=info");
     env_logger::init();
 
+    let config = Config::from_env()?;
+    if config.api_keys.is_empty() {
+        warn!("No Met Office API keys are configured, clients will have to send their own");
+    }
+    if config.access_tokens.is_disabled() {
+        warn!("Authentication is disabled, anyone who can reach the server can use it");
+    } else if config.access_tokens.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no access tokens are configured, set ACCESS_TOKENS or ACCESS_TOKEN_FILE, \
+             or AUTH_DISABLED=true to let anyone use the server",
+        ));
+    }
+    let met_api = MetApi::new(config.api_keys, config.met_api).map_err(io::Error::other)?;
+    let access_tokens = Arc::new(config.access_tokens);
+
+    let revalidating = met_api.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(REVALIDATE_INTERVAL);
+        loop {
+            interval.tick().await;
+            revalidating.revalidate().await;
+        }
+    });
+
+    let watch_list = Arc::new(WatchList::new(&config.watched_locations));
+    actix_web::rt::spawn(prefetch::run(
+        watch_list.clone(),
+        met_api.clone(),
+        config.prefetch_delay,
+    ));
+
     // Create Juniper schema
     let schema = std::sync::Arc::new(create_schema());
+    let image_cache = Arc::new(ImageCache::new(IMAGE_CACHE_CAPACITY));
+    let pollen = Arc::new(PollenApi::new(met_api.clone()));
 
     // Start http server
     HttpServer::new(move || {
         App::new()
             .data(schema.clone())
+            .data(met_api.clone())
+            .data(image_cache.clone())
+            .data(watch_list.clone())
+            .data(pollen.clone())
+            .wrap(BearerAuth::new(access_tokens.clone()))
             .wrap(middleware::Logger::default())
             .wrap(
                 Cors::new()
@@ -55,11 +117,23 @@ async fn main() -> io::Result<()> {
                     .max_age(3600)
                     .finish(),
             )
+            .service(web::resource("/health").route(web::get().to(health::health)))
             .service(
                 web::resource("/")
                     .route(web::post().to(graphql))
                     .route(web::get().to(graphiql)),
             )
+            .service(
+                web::resource(format!(
+                    "{}/{{kind}}/{{layer}}/{{format}}",
+                    LAYER_PROXY_PATH
+                ))
+                .route(web::get().to(image_proxy::layer_image)),
+            )
+            .service(
+                web::resource(format!("{}/{{forecast_period}}", CHART_PROXY_PATH))
+                    .route(web::get().to(image_proxy::surface_pressure_chart)),
+            )
     })
     .bind("127.0.0.1:8080")?
     .run()
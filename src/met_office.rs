@@ -1,25 +1,100 @@
+mod capabilities;
+mod circuit_breaker;
+mod daily_forecast;
+mod daily_forecasts;
 mod direction;
+#[cfg(feature = "disk-cache")]
+mod disk_cache;
+mod extreme_type;
 mod forecast;
 mod forecast_response;
 mod forecasts;
+mod hazard_risk;
+mod image;
 mod location;
+mod location_forecasts;
+mod map_layer;
+mod mountain_area;
+mod national_park;
+mod observation;
+mod observations;
+mod pressure_tendency;
+mod rate_limiter;
+mod regional_forecast;
+mod response_cache;
+mod retry;
+mod single_flight;
+mod surface_pressure;
+mod text_forecast;
+mod transport;
+mod uk_extremes;
 mod uv;
 mod visibility;
 mod weather_type;
 
+use futures::io::AsyncReadExt;
+use futures_timer::Delay;
+use isahc::http::header::CONTENT_TYPE;
+use isahc::http::StatusCode;
 use isahc::prelude::*;
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use thiserror::Error;
 
+pub use capabilities::Capabilities;
+use capabilities::{format_time_step, CapabilitiesResponse};
+use chrono::{NaiveDateTime, Utc};
+use circuit_breaker::CircuitBreaker;
+pub use circuit_breaker::{CircuitBreakerConfig, CircuitState, CircuitStatus};
+pub use daily_forecast::DailyForecast;
+pub use daily_forecasts::DailyForecasts;
 pub use forecast::{Forecast, ForecastConversionError};
-use forecast_response::ForecastResponse;
+use forecast_response::{DailyRep, ForecastResponse, ObservationRep};
 pub use forecasts::Forecasts;
+pub use image::Image;
 use location::LocationsResponse;
-pub use location::{Location, LocationId};
-use std::convert::TryInto;
+pub use location::{Location, LocationConversionError, LocationId};
+pub use location_forecasts::LocationForecasts;
+use map_layer::LayersResponse;
+pub use map_layer::{is_valid_layer_name, LayerRequest, MapLayer, LAYER_PROXY_PATH};
+use mountain_area::MountainAreaForecastResponse;
+pub use mountain_area::{MountainArea, MountainForecast, MountainForecastConversionError};
+use national_park::NationalParkForecastResponse;
+pub use national_park::{NationalPark, NationalParkForecast};
+pub use observation::{Observation, ObservationConversionError};
+pub use observations::Observations;
+use rate_limiter::RateLimiter;
+pub use rate_limiter::{ApiKey, Quota, RateLimit};
+use regional_forecast::RegionalForecastResponse;
+pub use regional_forecast::{Region, RegionalForecast};
+pub use response_cache::{CacheStats, Freshness, ResponseCacheConfig};
+use response_cache::{CachedBody, Expiry, ResponseCache, SITE_LIST_EXPIRY};
+pub use retry::RetryConfig;
+use serde::de::DeserializeOwned;
+use single_flight::SingleFlight;
+use std::convert::{TryFrom, TryInto};
+use surface_pressure::SurfacePressureResponse;
+pub use surface_pressure::{SurfacePressureChart, CHART_PROXY_PATH};
+use text_forecast::SitesResponse;
+#[cfg(test)]
+pub use transport::FakeTransport;
+use transport::Transport;
+use uk_extremes::UkExtremesResponse;
+pub use uk_extremes::{UkExtremes, UkExtremesConversionError};
 
-const MET_BASE: &str = "http://datapoint.metoffice.gov.uk/public/data";
+const DEFAULT_BASE_URL: &str = "https://datapoint.metoffice.gov.uk/public/data";
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_POLLEN_URL: &str =
+    "https://www.metoffice.gov.uk/weather/warnings-and-advice/seasonal-advice/pollen-forecast";
+/// The pollen forecast is published once a day, an hour old page is recent enough
+const POLLEN_TTL: Duration = Duration::from_secs(60 * 60);
 
 #[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum MetApiError {
     #[error("http error: {0:?}")]
     HttpError(#[from] isahc::Error),
@@ -29,43 +104,932 @@ pub enum MetApiError {
     IoError(#[from] std::io::Error),
     #[error("conversion error: {0:?}")]
     ForecastConversionError(#[from] ForecastConversionError),
+    #[error("conversion error: {0:?}")]
+    ObservationConversionError(#[from] ObservationConversionError),
+    #[error("conversion error: {0:?}")]
+    MountainForecastConversionError(#[from] MountainForecastConversionError),
+    #[error("conversion error: {0:?}")]
+    UkExtremesConversionError(#[from] UkExtremesConversionError),
     #[error("Could not parse number: {0:?}")]
     NumberParseError(#[from] std::num::ParseIntError),
+    #[error("expected an image, found {0}")]
+    NotAnImage(String),
+    #[error("the API key was rejected: {0}")]
+    InvalidApiKey(String),
+    #[error("rate limited: {0}")]
+    RateLimited(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("upstream returned status {0}: {1}")]
+    UpstreamUnavailable(u16, String),
+    #[error("no API key was given and none are configured")]
+    MissingApiKey,
+    #[error("every API key has used up its request budget")]
+    QuotaExhausted,
+    #[error("DataPoint kept failing, requests are paused for a while")]
+    CircuitOpen,
+    /// The error from a fetch that was shared with other requests
+    #[error("{0}")]
+    Shared(Arc<MetApiError>),
+}
+
+impl MetApiError {
+    /// Failures that may pass, where DataPoint couldn't be reached or had a server error
+    fn is_transient(&self) -> bool {
+        match self {
+            MetApiError::HttpError(error) => matches!(
+                error,
+                isahc::Error::Aborted
+                    | isahc::Error::ConnectFailed
+                    | isahc::Error::CouldntResolveHost
+                    | isahc::Error::Io(_)
+                    | isahc::Error::NoResponse
+                    | isahc::Error::ResponseBodyError(_)
+                    | isahc::Error::Timeout
+            ),
+            MetApiError::UpstreamUnavailable(status, _) => *status >= 500,
+            MetApiError::Shared(error) => error.is_transient(),
+            _ => false,
+        }
+    }
+
+    /// Errors made from a response DataPoint sent, which show it could be reached
+    fn is_response(&self) -> bool {
+        if let MetApiError::Shared(error) = self {
+            return error.is_response();
+        }
+        matches!(
+            self,
+            MetApiError::InvalidApiKey(_)
+                | MetApiError::RateLimited(_)
+                | MetApiError::NotFound(_)
+                | MetApiError::UpstreamUnavailable(_, _)
+        )
+    }
+
+    /// DataPoint has nothing at the path, such as observations for a site that only has forecasts
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            MetApiError::NotFound(_) => true,
+            MetApiError::Shared(error) => error.is_not_found(),
+            _ => false,
+        }
+    }
+
+    /// Failures where DataPoint can't be used for now, so an older response is better than none
+    fn is_unavailable(&self) -> bool {
+        if let MetApiError::Shared(error) = self {
+            return error.is_unavailable();
+        }
+        self.is_transient()
+            || matches!(
+                self,
+                MetApiError::CircuitOpen
+                    | MetApiError::RateLimited(_)
+                    | MetApiError::QuotaExhausted
+            )
+    }
+}
+
+/// Whether a response kept from before DataPoint became unavailable may be used
+#[derive(Clone, Copy, PartialEq)]
+enum StaleResponses {
+    Allow,
+    Refuse,
 }
 
 type Result<T> = std::result::Result<T, MetApiError>;
 
+/// How much of an error response body is kept for diagnostics
+const BODY_EXCERPT_LENGTH: usize = 200;
+
+async fn body_excerpt(response: &mut Response<Body>) -> String {
+    let body = response.text_async().await.unwrap_or_default();
+    body.chars().take(BODY_EXCERPT_LENGTH).collect()
+}
+
+/// DataPoint answers failures with an HTML page, so check the status before parsing the body
+async fn check_status(mut response: Response<Body>) -> Result<Response<Body>> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = body_excerpt(&mut response).await;
+    Err(match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => MetApiError::InvalidApiKey(body),
+        StatusCode::TOO_MANY_REQUESTS => MetApiError::RateLimited(body),
+        StatusCode::NOT_FOUND => MetApiError::NotFound(body),
+        _ => MetApiError::UpstreamUnavailable(status.as_u16(), body),
+    })
+}
+
+fn time_parameter(time: Option<NaiveDateTime>) -> String {
+    time.map(|time| format!("&time={}", format_time_step(time)))
+        .unwrap_or_default()
+}
+
+/// Where DataPoint is, overridden to point the service at a local fake in tests
+#[derive(Clone, Debug)]
+pub struct MetApiConfig {
+    pub base_url: String,
+    /// The longest a whole request may take, including reading the response
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    pub retry: RetryConfig,
+    pub circuit_breaker: CircuitBreakerConfig,
+    pub cache: ResponseCacheConfig,
+    /// The Met Office website page the pollen forecast is scraped from
+    pub pollen_url: String,
+}
+
+impl MetApiConfig {
+    pub fn new(base_url: &str) -> MetApiConfig {
+        MetApiConfig {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            retry: RetryConfig::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            cache: ResponseCacheConfig::default(),
+            pollen_url: DEFAULT_POLLEN_URL.to_string(),
+        }
+    }
+}
+
+impl Default for MetApiConfig {
+    fn default() -> Self {
+        MetApiConfig::new(DEFAULT_BASE_URL)
+    }
+}
+
+/// A handle to DataPoint, clones share the API keys, their budgets, the response cache and the
+/// HTTP client, so connections to DataPoint are reused
+#[derive(Clone)]
 pub struct MetApi {
-    pub api_key: String,
+    api_keys: Arc<[ApiKey]>,
+    next_key: Arc<AtomicUsize>,
+    config: MetApiConfig,
+    transport: Arc<dyn Transport>,
+    cache: Arc<ResponseCache>,
+    rate_limiter: Arc<RateLimiter>,
+    circuit_breaker: Arc<CircuitBreaker>,
+    /// Kept apart from DataPoint's, as the website can fail while DataPoint is fine
+    website_circuit_breaker: Arc<CircuitBreaker>,
+    single_flight: Arc<SingleFlight<CachedBody>>,
+    /// Set when a client sent its own key, whose requests aren't shared with anyone else's
+    client_key: bool,
+    /// Paths that were served stale, to be refetched once DataPoint is back
+    stale_paths: Arc<Mutex<HashMap<String, Expiry>>>,
 }
 
 impl MetApi {
-    pub fn new(api_key: String) -> MetApi {
-        MetApi { api_key }
+    /// The keys are used in turn so requests are spread across their quotas
+    pub fn new(api_keys: Vec<ApiKey>, config: MetApiConfig) -> Result<MetApi> {
+        let client = HttpClient::builder()
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout)
+            .build()?;
+        Ok(MetApi::with_transport(api_keys, config, Arc::new(client)))
+    }
+
+    /// Sends requests through `transport` rather than an HTTP client, such as a fake in tests
+    pub fn with_transport(
+        api_keys: Vec<ApiKey>,
+        config: MetApiConfig,
+        transport: Arc<dyn Transport>,
+    ) -> MetApi {
+        MetApi {
+            rate_limiter: Arc::new(RateLimiter::new(&api_keys)),
+            api_keys: api_keys.into(),
+            next_key: Arc::new(AtomicUsize::new(0)),
+            transport,
+            cache: Arc::new(ResponseCache::new(config.cache.clone())),
+            circuit_breaker: Arc::new(CircuitBreaker::new(config.circuit_breaker.clone())),
+            website_circuit_breaker: Arc::new(CircuitBreaker::new(config.circuit_breaker.clone())),
+            single_flight: Arc::new(SingleFlight::default()),
+            client_key: false,
+            stale_paths: Arc::new(Mutex::new(HashMap::new())),
+            config,
+        }
+    }
+
+    /// Uses a key sent by the client instead of the server's keys
+    pub fn with_api_key(&self, api_key: String) -> MetApi {
+        MetApi {
+            api_keys: vec![ApiKey::new(&api_key)].into(),
+            next_key: Arc::new(AtomicUsize::new(0)),
+            config: self.config.clone(),
+            transport: self.transport.clone(),
+            cache: self.cache.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            circuit_breaker: self.circuit_breaker.clone(),
+            website_circuit_breaker: self.website_circuit_breaker.clone(),
+            single_flight: self.single_flight.clone(),
+            client_key: true,
+            stale_paths: self.stale_paths.clone(),
+        }
+    }
+
+    /// Takes a request from the budget of the next key that has one left
+    fn api_key(&self) -> Result<&str> {
+        if self.api_keys.is_empty() {
+            return Err(MetApiError::MissingApiKey);
+        }
+        let next_key = self.next_key.fetch_add(1, Ordering::Relaxed);
+        (0..self.api_keys.len())
+            .map(|offset| &self.api_keys[(next_key + offset) % self.api_keys.len()])
+            .find(|api_key| self.rate_limiter.try_acquire(api_key))
+            .map(|api_key| api_key.key.as_str())
+            .ok_or(MetApiError::QuotaExhausted)
+    }
+
+    pub fn quota(&self) -> Vec<Quota> {
+        self.api_keys
+            .iter()
+            .map(|api_key| self.rate_limiter.quota(api_key))
+            .collect()
+    }
+
+    /// The DataPoint URL for a path, which may already have query parameters
+    fn uri(&self, path: &str) -> Result<String> {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        Ok(format!(
+            "{}/{}{}key={}",
+            self.config.base_url,
+            path,
+            separator,
+            self.api_key()?
+        ))
+    }
+
+    async fn send(&self, uri: &str) -> Result<Response<Body>> {
+        let response = self.transport.get(uri).await?;
+        check_status(response).await
+    }
+
+    /// Every request is a GET, so transient failures are safe to retry. `uri` is called for each
+    /// attempt, as each takes a request from an API key's budget
+    async fn request<U>(
+        &self,
+        breaker: &CircuitBreaker,
+        path: &str,
+        uri: U,
+    ) -> Result<Response<Body>>
+    where
+        U: Fn() -> Result<String>,
+    {
+        if !breaker.allow() {
+            return Err(MetApiError::CircuitOpen);
+        }
+        let mut retry = 0;
+        loop {
+            let result = match uri() {
+                Ok(uri) => self.send(&uri).await,
+                Err(error) => Err(error),
+            };
+            match &result {
+                Err(error) if error.is_transient() && retry < self.config.retry.max_retries => {
+                    let backoff = self.config.retry.backoff(retry);
+                    warn!("retrying {} in {:?} after {}", path, backoff, error);
+                    Delay::new(backoff).await;
+                    retry += 1;
+                    continue;
+                }
+                Err(error) if error.is_transient() => breaker.record_failure(),
+                Err(error) if error.is_response() => breaker.record_success(),
+                Ok(_) => breaker.record_success(),
+                // Nothing reached upstream, such as when the keys are used up
+                Err(_) => breaker.release_trial(),
+            }
+            return result;
+        }
+    }
+
+    async fn make_request(&self, path: &str) -> Result<Response<Body>> {
+        self.request(&self.circuit_breaker, path, || self.uri(path))
+            .await
+    }
+
+    async fn fetch_body(&self, path: &str, expiry: Expiry) -> Result<CachedBody> {
+        let body = self.make_request(path).await?.text_async().await?;
+        let ttl = expiry.ttl(&body, Utc::now().naive_utc());
+        Ok(self.cache.insert(path.to_string(), body, ttl))
+    }
+
+    /// Responses are cached by path, which doesn't include the API key, and concurrent requests
+    /// for the same path share one fetch whatever they parse the body into. Requests with a
+    /// client's key do neither, so a rejected key's error isn't shared with other callers.
+    async fn get_body(
+        &self,
+        path: &str,
+        expiry: Expiry,
+        stale: StaleResponses,
+    ) -> Result<CachedBody> {
+        if self.client_key {
+            let body = self.make_request(path).await?.text_async().await?;
+            return Ok(CachedBody {
+                body: body.into(),
+                freshness: Freshness {
+                    stale: false,
+                    fetched_at: Utc::now().naive_utc(),
+                },
+            });
+        }
+        if let Some(cached) = self.cache.get(path) {
+            return Ok(cached);
+        }
+        let met_api = self.clone();
+        let fetch_path = path.to_string();
+        let fetched = self
+            .single_flight
+            .run(path.to_string(), async move {
+                met_api.fetch_body(&fetch_path, expiry).await
+            })
+            .await;
+        match fetched {
+            Err(error) if stale == StaleResponses::Allow && error.is_unavailable() => {
+                let cached = self.cache.get_stale(path).ok_or(error)?;
+                warn!("serving a stale response for {}", path);
+                self.stale_paths
+                    .lock()
+                    .unwrap()
+                    .insert(path.to_string(), expiry);
+                Ok(cached)
+            }
+            result => result,
+        }
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: String, expiry: Expiry) -> Result<T> {
+        let cached = self.get_body(&path, expiry, StaleResponses::Refuse).await?;
+        Ok(serde_json::from_str(&cached.body)?)
+    }
+
+    /// Falls back to the last response while DataPoint is unavailable
+    async fn get_json_or_stale<T: DeserializeOwned>(
+        &self,
+        path: String,
+        expiry: Expiry,
+    ) -> Result<(T, Freshness)> {
+        let cached = self.get_body(&path, expiry, StaleResponses::Allow).await?;
+        Ok((serde_json::from_str(&cached.body)?, cached.freshness))
+    }
+
+    /// Fetches a response and converts it from DataPoint's shape
+    async fn get_converted<R, T>(
+        &self,
+        path: String,
+        expiry: Expiry,
+        stale: StaleResponses,
+    ) -> Result<(T, Freshness)>
+    where
+        R: DeserializeOwned,
+        T: TryFrom<R>,
+        MetApiError: From<T::Error>,
+    {
+        let cached = self.get_body(&path, expiry, stale).await?;
+        let response: R = serde_json::from_str(&cached.body)?;
+        Ok((T::try_from(response)?, cached.freshness))
+    }
+
+    /// Drops responses too old to be served stale, then refetches the responses that were served
+    /// stale, until one fails because DataPoint is still unavailable
+    pub async fn revalidate(&self) {
+        self.cache.prune();
+        let stale_paths: Vec<(String, Expiry)> = self
+            .stale_paths
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, expiry)| (path.clone(), *expiry))
+            .collect();
+        for (path, expiry) in stale_paths {
+            if self.cache.get_fresh(&path).is_none() {
+                match self.fetch_body(&path, expiry).await {
+                    Ok(_) => info!("Refreshed the stale response for {}", path),
+                    Err(error) if error.is_unavailable() => {
+                        debug!("DataPoint is still unavailable: {}", error);
+                        return;
+                    }
+                    Err(error) => warn!("could not refresh {}: {}", path, error),
+                }
+            }
+            self.stale_paths.lock().unwrap().remove(&path);
+        }
+    }
+
+    async fn fetch_image(&self, path: &str) -> Result<Image> {
+        let mut response = self.make_request(path).await?;
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        if !content_type.starts_with("image/") {
+            return Err(MetApiError::NotAnImage(content_type));
+        }
+        let mut data = Vec::new();
+        response.body_mut().read_to_end(&mut data).await?;
+        Ok(Image { content_type, data })
+    }
+
+    pub async fn forecast_site_list(&self) -> Result<Vec<Location>> {
+        let (response, freshness): (LocationsResponse, _) = self
+            .get_json_or_stale("val/wxfcs/all/json/sitelist".to_string(), SITE_LIST_EXPIRY)
+            .await?;
+        let mut locations = response.locations.location;
+        for location in &mut locations {
+            location.set_freshness(freshness);
+        }
+        Ok(locations)
+    }
+
+    pub async fn forecast_capabilities(&self) -> Result<Capabilities> {
+        let response: CapabilitiesResponse = self
+            .get_json(
+                "val/wxfcs/all/json/capabilities?res=3hourly".to_string(),
+                Expiry::Default,
+            )
+            .await?;
+        Ok(response.try_into()?)
+    }
+
+    pub async fn forecast(
+        &self,
+        location_id: LocationId,
+        time: Option<NaiveDateTime>,
+    ) -> Result<Forecasts> {
+        let (mut forecasts, freshness): (Forecasts, _) = self
+            .get_converted::<ForecastResponse, _>(
+                format!(
+                    "val/wxfcs/all/json/{}?res=3hourly{}",
+                    location_id,
+                    time_parameter(time)
+                ),
+                Expiry::DataDate,
+                StaleResponses::Allow,
+            )
+            .await?;
+        forecasts.set_freshness(freshness);
+        Ok(forecasts)
+    }
+
+    pub async fn site_forecasts(
+        &self,
+        location_id: LocationId,
+        time: Option<NaiveDateTime>,
+    ) -> Result<Vec<LocationForecasts>> {
+        let (mut site_forecasts, freshness): (Vec<LocationForecasts>, _) = self
+            .get_converted::<ForecastResponse, _>(
+                format!(
+                    "val/wxfcs/all/json/{}?res=3hourly{}",
+                    location_id,
+                    time_parameter(time)
+                ),
+                Expiry::DataDate,
+                StaleResponses::Allow,
+            )
+            .await?;
+        for location_forecasts in &mut site_forecasts {
+            location_forecasts.set_freshness(freshness);
+        }
+        Ok(site_forecasts)
     }
 
-    fn make_request(&self, uri: String) -> Result<Response<Body>> {
-        Ok(isahc::get(uri)?)
+    pub async fn daily_forecast(&self, location_id: LocationId) -> Result<DailyForecasts> {
+        let (daily_forecasts, _) = self
+            .get_converted::<ForecastResponse<DailyRep>, _>(
+                format!("val/wxfcs/all/json/{}?res=daily", location_id),
+                Expiry::DataDate,
+                StaleResponses::Refuse,
+            )
+            .await?;
+        Ok(daily_forecasts)
     }
 
-    pub fn forecast_site_list(&self) -> Result<Vec<Location>> {
-        let response: LocationsResponse = self
-            .make_request(format!(
-                "{}/val/wxfcs/all/json/sitelist?key={}",
-                MET_BASE, self.api_key
-            ))?
-            .json()?;
+    pub async fn observation_site_list(&self) -> Result<Vec<Location>> {
+        let (response, freshness): (LocationsResponse, _) = self
+            .get_json_or_stale("val/wxobs/all/json/sitelist".to_string(), SITE_LIST_EXPIRY)
+            .await?;
+        let mut locations = response.locations.location;
+        for location in &mut locations {
+            location.set_freshness(freshness);
+        }
+        Ok(locations)
+    }
+
+    pub async fn observations(&self, location_id: LocationId) -> Result<Observations> {
+        let (observations, _) = self
+            .get_converted::<ForecastResponse<ObservationRep>, _>(
+                format!("val/wxobs/all/json/{}?res=hourly", location_id),
+                Expiry::DataDate,
+                StaleResponses::Refuse,
+            )
+            .await?;
+        Ok(observations)
+    }
+
+    pub async fn regional_forecast_site_list(&self) -> Result<Vec<Region>> {
+        let response: SitesResponse<Region> = self
+            .get_json(
+                "txt/wxfcs/regionalforecast/json/sitelist".to_string(),
+                SITE_LIST_EXPIRY,
+            )
+            .await?;
         Ok(response.locations.location)
     }
 
-    pub fn forecast(&self, location_id: LocationId) -> Result<Forecasts> {
-        let mut response = self.make_request(format!(
-            "{}/val/wxfcs/all/json/{}?res=3hourly&key={}",
-            MET_BASE, location_id, self.api_key
-        ))?;
+    pub async fn regional_forecast(&self, region_id: &str) -> Result<RegionalForecast> {
+        let response: RegionalForecastResponse = self
+            .get_json(
+                format!("txt/wxfcs/regionalforecast/json/{}", region_id),
+                Expiry::Default,
+            )
+            .await?;
+        Ok(response.try_into()?)
+    }
+
+    /// Looks up the regional forecast by region code, as found in `Location.region`
+    pub async fn regional_forecast_by_name(&self, name: &str) -> Result<Option<RegionalForecast>> {
+        let region = self
+            .regional_forecast_site_list()
+            .await?
+            .into_iter()
+            .find(|region| region.name == name);
+        match region {
+            Some(region) => Ok(Some(self.regional_forecast(&region.id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn national_park_site_list(&self) -> Result<Vec<NationalPark>> {
+        let response: SitesResponse<NationalPark> = self
+            .get_json(
+                "txt/wxfcs/nationalpark/json/sitelist".to_string(),
+                SITE_LIST_EXPIRY,
+            )
+            .await?;
+        Ok(response.locations.location)
+    }
+
+    pub async fn national_park_forecast(&self, park_id: &str) -> Result<NationalParkForecast> {
+        let response: NationalParkForecastResponse = self
+            .get_json(
+                format!("txt/wxfcs/nationalpark/json/{}", park_id),
+                Expiry::Default,
+            )
+            .await?;
+        Ok(response.try_into()?)
+    }
+
+    pub async fn mountain_area_site_list(&self) -> Result<Vec<MountainArea>> {
+        let response: SitesResponse<MountainArea> = self
+            .get_json(
+                "txt/wxfcs/mountainarea/json/sitelist".to_string(),
+                SITE_LIST_EXPIRY,
+            )
+            .await?;
+        Ok(response.locations.location)
+    }
+
+    pub async fn mountain_area_forecast(&self, area_id: &str) -> Result<MountainForecast> {
+        let response: MountainAreaForecastResponse = self
+            .get_json(
+                format!("txt/wxfcs/mountainarea/json/{}", area_id),
+                Expiry::Default,
+            )
+            .await?;
+        Ok(response.try_into()?)
+    }
+
+    pub async fn uk_extremes(&self) -> Result<UkExtremes> {
+        let response: UkExtremesResponse = self
+            .get_json(
+                "txt/wxobs/ukextremes/json/latest".to_string(),
+                Expiry::Default,
+            )
+            .await?;
+        Ok(response.try_into()?)
+    }
 
-        let forecast_response: ForecastResponse = response.json()?;
+    pub async fn forecast_layers(&self) -> Result<Vec<MapLayer>> {
+        let response: LayersResponse = self
+            .get_json(
+                "layer/wxfcs/all/json/capabilities".to_string(),
+                Expiry::Default,
+            )
+            .await?;
+        Ok(response.try_into()?)
+    }
+
+    pub async fn observation_layers(&self) -> Result<Vec<MapLayer>> {
+        let response: LayersResponse = self
+            .get_json(
+                "layer/wxobs/all/json/capabilities".to_string(),
+                Expiry::Default,
+            )
+            .await?;
+        Ok(response.try_into()?)
+    }
+
+    pub async fn layer_image(
+        &self,
+        layer_name: &str,
+        image_format: &str,
+        request: &LayerRequest,
+    ) -> Result<Image> {
+        self.fetch_image(&format!(
+            "layer/{}/{}/{}?{}",
+            request.kind(),
+            layer_name,
+            image_format,
+            request.query()
+        ))
+        .await
+    }
+
+    pub async fn surface_pressure_charts(&self) -> Result<Vec<SurfacePressureChart>> {
+        let response: SurfacePressureResponse = self
+            .get_json(
+                "image/wxfcs/surfacepressure/json/capabilities".to_string(),
+                Expiry::Default,
+            )
+            .await?;
+        Ok(response.try_into()?)
+    }
+
+    pub async fn surface_pressure_chart(&self, forecast_period: u32) -> Result<Image> {
+        self.fetch_image(&format!(
+            "image/wxfcs/surfacepressure/gif?timestep={}",
+            forecast_period
+        ))
+        .await
+    }
+
+    /// The pollen forecast page, cached by its URL and fetched without an API key as it isn't
+    /// part of DataPoint
+    pub async fn pollen_forecast_page(&self) -> Result<Arc<str>> {
+        let url = self.config.pollen_url.clone();
+        if let Some(cached) = self.cache.get(&url) {
+            return Ok(cached.body);
+        }
+        let met_api = self.clone();
+        let cached = self
+            .single_flight
+            .run(url.clone(), async move {
+                let body = met_api
+                    .request(&met_api.website_circuit_breaker, &url, || Ok(url.clone()))
+                    .await?
+                    .text_async()
+                    .await?;
+                Ok(met_api.cache.insert(url, body, POLLEN_TTL))
+            })
+            .await?;
+        Ok(cached.body)
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    pub fn circuit_status(&self) -> CircuitStatus {
+        self.circuit_breaker.status()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn response(status: u16, body: &'static str) -> Response<Body> {
+        Response::builder()
+            .status(status)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_api_keys_are_used_in_turn() {
+        let met_api = MetApi::new(
+            vec![ApiKey::new("a"), ApiKey::new("b")],
+            MetApiConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(met_api.api_key().unwrap(), "a");
+        assert_eq!(met_api.clone().api_key().unwrap(), "b");
+        assert_eq!(met_api.api_key().unwrap(), "a");
+        assert_eq!(
+            met_api.with_api_key("c".to_string()).api_key().unwrap(),
+            "c"
+        );
+    }
+
+    #[test]
+    fn test_quota_exhausted() {
+        let api_key = ApiKey {
+            key: "a".to_string(),
+            rate_limit: RateLimit {
+                per_minute: 1,
+                per_day: 1,
+            },
+        };
+        let met_api =
+            MetApi::new(vec![api_key, ApiKey::new("b")], MetApiConfig::default()).unwrap();
+        assert_eq!(met_api.api_key().unwrap(), "a");
+        assert_eq!(met_api.api_key().unwrap(), "b");
+        assert_eq!(met_api.api_key().unwrap(), "b");
+
+        let met_api = met_api.with_api_key("a".to_string());
+        assert!(matches!(
+            met_api.api_key(),
+            Err(MetApiError::QuotaExhausted)
+        ));
+    }
+
+    #[test]
+    fn test_missing_api_key() {
+        let met_api = MetApi::new(Vec::new(), MetApiConfig::default()).unwrap();
+        assert!(matches!(met_api.api_key(), Err(MetApiError::MissingApiKey)));
+    }
+
+    #[test]
+    fn test_stale_response_while_unavailable() {
+        let path = "val/wxfcs/all/json/sitelist";
+        let (met_api, _) = MetApi::fake(FakeTransport::default().with_status(path, 503));
+        let body = r#"{"Locations": {"Location": [{"id": "3", "name": "Here", "latitude": "1", "longitude": "2"}]}}"#;
+        met_api
+            .cache
+            .insert(path.to_string(), body.to_string(), Duration::from_secs(0));
+
+        let locations = block_on(met_api.forecast_site_list()).unwrap();
+        assert_eq!(locations[0].name, "Here");
+        assert!(locations[0].stale);
+        assert!(met_api.stale_paths.lock().unwrap().contains_key(path));
+        assert!(block_on(met_api.forecast_capabilities()).is_err());
+
+        block_on(met_api.revalidate());
+        assert!(met_api.stale_paths.lock().unwrap().contains_key(path));
+    }
+
+    #[test]
+    fn test_forecast_site_list() {
+        let (met_api, transport) = MetApi::fake(FakeTransport::default().with(
+            "val/wxfcs/all/json/sitelist",
+            include_str!("../fixtures/forecast_sitelist.json"),
+        ));
+        let locations = block_on(met_api.forecast_site_list()).unwrap();
+        assert_eq!(locations.len(), 3);
+        assert_eq!(locations[2].id, "310069");
+        assert_eq!(locations[2].name, "Exeter");
+        assert_eq!(locations[2].region.as_deref(), Some("sw"));
+        assert!(!locations[2].stale);
+        assert!(locations[2].fetched_at.is_some());
+
+        block_on(met_api.forecast_site_list()).unwrap();
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_forecast() {
+        let (met_api, transport) = MetApi::fake(
+            FakeTransport::default()
+                .with(
+                    "val/wxfcs/all/json/310069?res=3hourly",
+                    include_str!("../fixtures/forecast.json"),
+                )
+                .with_status("val/wxfcs/all/json/3840?res=3hourly", 503),
+        );
+        let forecasts = block_on(met_api.forecast(LocationId::Location(310069), None)).unwrap();
+        assert_eq!(forecasts.len(), 3);
+        assert!(!forecasts.is_stale());
+        let forecast = serde_json::to_value(&forecasts[1]).unwrap();
+        assert_eq!(forecast["location_id"], 310069);
+        assert_eq!(forecast["timestamp"], "2020-10-17T18:00:00");
+        assert_eq!(forecast["temperature"], 10);
+
+        assert!(matches!(
+            block_on(met_api.forecast(LocationId::Location(3840), None)),
+            Err(MetApiError::UpstreamUnavailable(503, _))
+        ));
+        assert!(matches!(
+            block_on(met_api.forecast(LocationId::Location(1), None)),
+            Err(MetApiError::NotFound(_))
+        ));
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_forecast_and_site_forecasts_share_a_fetch() {
+        let (transport, resume) = FakeTransport::default()
+            .with(
+                "val/wxfcs/all/json/310069?res=3hourly",
+                include_str!("../fixtures/forecast.json"),
+            )
+            .paused();
+        let (met_api, transport) = MetApi::fake(transport);
+        let location_id = LocationId::Location(310069);
+        let (forecasts, site_forecasts, _) = block_on(async {
+            futures::join!(
+                met_api.forecast(location_id, None),
+                met_api.site_forecasts(location_id, None),
+                async { resume.send(()).unwrap() }
+            )
+        });
+        assert_eq!(forecasts.unwrap().len(), 3);
+        assert_eq!(site_forecasts.unwrap()[0].forecasts.len(), 3);
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[test]
+    fn test_client_keys_dont_share_fetches() {
+        let (transport, resume) = FakeTransport::default()
+            .with(
+                "val/wxfcs/all/json/sitelist",
+                include_str!("../fixtures/forecast_sitelist.json"),
+            )
+            .rejecting_key("bad")
+            .paused();
+        let (met_api, transport) = MetApi::fake(transport);
+        let client = met_api.with_api_key("bad".to_string());
+        let (server_locations, client_locations, _) = block_on(async {
+            futures::join!(
+                met_api.forecast_site_list(),
+                client.forecast_site_list(),
+                async { resume.send(()).unwrap() }
+            )
+        });
+        assert_eq!(server_locations.unwrap().len(), 3);
+        assert!(matches!(
+            client_locations,
+            Err(MetApiError::InvalidApiKey(_))
+        ));
+        assert_eq!(transport.requests().len(), 2);
+
+        let good_client = met_api.with_api_key("good".to_string());
+        assert_eq!(block_on(good_client.forecast_site_list()).unwrap().len(), 3);
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[test]
+    fn test_half_open_trial_answered_with_an_error() {
+        let transport = FakeTransport::default().with_status("val/wxfcs/all/json/sitelist", 503);
+        let (met_api, _) = MetApi::fake_with(transport, |config| {
+            config.circuit_breaker.failure_threshold = 1;
+            config.circuit_breaker.cooldown = Duration::from_millis(10);
+        });
+
+        assert!(block_on(met_api.forecast_site_list()).is_err());
+        assert_eq!(met_api.circuit_status().state, CircuitState::Open);
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(matches!(
+            block_on(met_api.observation_site_list()),
+            Err(MetApiError::NotFound(_))
+        ));
+        assert_eq!(met_api.circuit_status().state, CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_check_status() {
+        assert!(block_on(check_status(response(200, "{}"))).is_ok());
+        assert!(matches!(
+            block_on(check_status(response(403, "<html>Forbidden</html>"))),
+            Err(MetApiError::InvalidApiKey(body)) if body == "<html>Forbidden</html>"
+        ));
+        assert!(matches!(
+            block_on(check_status(response(429, ""))),
+            Err(MetApiError::RateLimited(_))
+        ));
+        assert!(matches!(
+            block_on(check_status(response(404, ""))),
+            Err(MetApiError::NotFound(_))
+        ));
+        assert!(matches!(
+            block_on(check_status(response(503, ""))),
+            Err(MetApiError::UpstreamUnavailable(503, _))
+        ));
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        assert!(MetApiError::HttpError(isahc::Error::Timeout).is_transient());
+        assert!(MetApiError::UpstreamUnavailable(503, String::new()).is_transient());
+        assert!(!MetApiError::NotFound(String::new()).is_transient());
+        assert!(!MetApiError::HttpError(isahc::Error::InvalidUtf8).is_transient());
+    }
 
-        Ok(forecast_response.try_into()?)
+    #[test]
+    fn test_body_excerpt_is_truncated() {
+        let response = Response::builder()
+            .status(500)
+            .body(Body::from("x".repeat(1000)))
+            .unwrap();
+        match block_on(check_status(response)) {
+            Err(MetApiError::UpstreamUnavailable(_, excerpt)) => {
+                assert_eq!(excerpt.len(), BODY_EXCERPT_LENGTH)
+            }
+            _ => panic!("expected an upstream error"),
+        }
     }
 }
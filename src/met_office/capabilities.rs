@@ -0,0 +1,96 @@
+use crate::met_office::forecast_response::one_or_many;
+use crate::met_office::ForecastConversionError;
+use chrono::NaiveDateTime;
+use juniper::GraphQLObject;
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+const TIME_STEP_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CapabilitiesResponse {
+    pub resource: Resource,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Resource {
+    #[serde(rename = "dataDate")]
+    pub data_date: String,
+    pub res: String,
+    #[serde(rename = "TimeSteps")]
+    pub time_steps: TimeSteps,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimeSteps {
+    #[serde(rename = "TS", deserialize_with = "one_or_many")]
+    pub ts: Vec<String>,
+}
+
+/// The time steps currently available for a forecast resolution
+#[derive(GraphQLObject)]
+pub struct Capabilities {
+    data_date: NaiveDateTime,
+    resolution: String,
+    time_steps: Vec<NaiveDateTime>,
+}
+
+pub fn parse_time_step(time_step: &str) -> Result<NaiveDateTime, ForecastConversionError> {
+    NaiveDateTime::parse_from_str(time_step, TIME_STEP_FORMAT)
+        .map_err(|_| ForecastConversionError::DateTimeParseError(time_step.to_string()))
+}
+
+pub fn format_time_step(time_step: NaiveDateTime) -> String {
+    time_step.format(TIME_STEP_FORMAT).to_string()
+}
+
+impl TryFrom<CapabilitiesResponse> for Capabilities {
+    type Error = ForecastConversionError;
+
+    fn try_from(value: CapabilitiesResponse) -> Result<Self, Self::Error> {
+        let resource = value.resource;
+        let time_steps: Result<Vec<_>, _> = resource
+            .time_steps
+            .ts
+            .iter()
+            .map(|time_step| parse_time_step(time_step))
+            .collect();
+        Ok(Self {
+            data_date: parse_time_step(&resource.data_date)?,
+            resolution: resource.res,
+            time_steps: time_steps?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_capabilities() {
+        let response: CapabilitiesResponse =
+            serde_json::from_str(include_str!("../../fixtures/capabilities.json")).unwrap();
+        let capabilities: Capabilities = response.try_into().unwrap();
+        assert_eq!(capabilities.resolution, "3hourly");
+        assert_eq!(
+            capabilities.data_date,
+            parse_time_step("2020-10-17T15:00:00Z").unwrap()
+        );
+        assert_eq!(capabilities.time_steps.len(), 4);
+        assert_eq!(
+            format_time_step(capabilities.time_steps[3]),
+            "2020-10-18T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn test_invalid_time_step() {
+        assert!(matches!(
+            parse_time_step("2020-10-17"),
+            Err(ForecastConversionError::DateTimeParseError(_))
+        ));
+    }
+}
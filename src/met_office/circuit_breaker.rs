@@ -0,0 +1,178 @@
+//! Stops calling DataPoint for a while once it keeps failing, so queries fail fast instead of
+//! waiting on timeouts and retries
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerConfig {
+    /// Failed requests in a row before the breaker opens
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before a request is let through to test DataPoint
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    /// The cooldown is over and the next request decides whether the breaker closes
+    HalfOpen,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitStatus {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// Seconds until a request is let through to test DataPoint again
+    pub retry_in_seconds: Option<u64>,
+}
+
+#[derive(Default)]
+struct Breaker {
+    failures: u32,
+    open_until: Option<Instant>,
+    /// When the half open test request went out, another is allowed if it never reports back
+    trial_started: Option<Instant>,
+}
+
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    breaker: Mutex<Breaker>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> CircuitBreaker {
+        CircuitBreaker {
+            config,
+            breaker: Mutex::new(Breaker::default()),
+        }
+    }
+
+    /// Whether a request may go to DataPoint
+    pub fn allow(&self) -> bool {
+        self.allow_at(Instant::now())
+    }
+
+    fn allow_at(&self, now: Instant) -> bool {
+        let mut breaker = self.breaker.lock().unwrap();
+        match breaker.open_until {
+            None => true,
+            Some(open_until) if now < open_until => false,
+            Some(_) => match breaker.trial_started {
+                Some(started) if now < started + self.config.cooldown => false,
+                _ => {
+                    breaker.trial_started = Some(now);
+                    true
+                }
+            },
+        }
+    }
+
+    pub fn record_success(&self) {
+        *self.breaker.lock().unwrap() = Breaker::default();
+    }
+
+    /// Lets another request test DataPoint when the half open one never got to it
+    pub fn release_trial(&self) {
+        self.breaker.lock().unwrap().trial_started = None;
+    }
+
+    pub fn record_failure(&self) {
+        self.record_failure_at(Instant::now())
+    }
+
+    fn record_failure_at(&self, now: Instant) {
+        let mut breaker = self.breaker.lock().unwrap();
+        breaker.failures = breaker.failures.saturating_add(1);
+        breaker.trial_started = None;
+        if breaker.failures >= self.config.failure_threshold || breaker.open_until.is_some() {
+            breaker.open_until = Some(now + self.config.cooldown);
+        }
+    }
+
+    pub fn status(&self) -> CircuitStatus {
+        self.status_at(Instant::now())
+    }
+
+    fn status_at(&self, now: Instant) -> CircuitStatus {
+        let breaker = self.breaker.lock().unwrap();
+        let state = match breaker.open_until {
+            None => CircuitState::Closed,
+            Some(open_until) if now < open_until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        };
+        CircuitStatus {
+            state,
+            consecutive_failures: breaker.failures,
+            retry_in_seconds: breaker
+                .open_until
+                .filter(|open_until| now < *open_until)
+                .map(|open_until| (open_until - now).as_secs()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(30),
+        })
+    }
+
+    #[test]
+    fn test_opens_after_repeated_failures() {
+        let breaker = breaker();
+        let now = Instant::now();
+        breaker.record_failure_at(now);
+        assert!(breaker.allow_at(now));
+        breaker.record_success();
+        breaker.record_failure_at(now);
+        assert!(breaker.allow_at(now));
+        breaker.record_failure_at(now);
+        assert!(!breaker.allow_at(now));
+
+        let status = breaker.status_at(now + Duration::from_secs(10));
+        assert_eq!(status.state, CircuitState::Open);
+        assert_eq!(status.consecutive_failures, 2);
+        assert_eq!(status.retry_in_seconds, Some(20));
+    }
+
+    #[test]
+    fn test_half_open_lets_one_request_through() {
+        let breaker = breaker();
+        let now = Instant::now();
+        breaker.record_failure_at(now);
+        breaker.record_failure_at(now);
+
+        let later = now + Duration::from_secs(30);
+        assert_eq!(breaker.status_at(later).state, CircuitState::HalfOpen);
+        assert!(breaker.allow_at(later));
+        assert!(!breaker.allow_at(later));
+        breaker.record_failure_at(later);
+        assert!(!breaker.allow_at(later + Duration::from_secs(1)));
+
+        let much_later = later + Duration::from_secs(30);
+        assert!(breaker.allow_at(much_later));
+        breaker.release_trial();
+        assert!(breaker.allow_at(much_later));
+        breaker.record_success();
+        assert_eq!(breaker.status_at(much_later).state, CircuitState::Closed);
+        assert!(breaker.allow_at(much_later));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::met_office::forecast_response::{DailyRep, DayRep, NightRep};
+use crate::met_office::{
+    direction::Direction, uv::UvIndex, visibility::Visibility, weather_type::WeatherType,
+    ForecastConversionError,
+};
+use chrono::NaiveDate;
+use juniper::{GraphQLEnum, GraphQLObject};
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Eq, PartialEq, GraphQLEnum, Serialize)]
+pub enum DayPeriod {
+    Day,
+    Night,
+}
+
+#[derive(Clone, GraphQLObject, Serialize)]
+pub struct DailyForecast {
+    location_id: i32,
+    date: NaiveDate,
+    period: DayPeriod,
+    /// Day maximum or night minimum, depending on the period
+    feels_like_temperature: i32,
+    /// Gust at noon for the day, or at midnight for the night
+    wind_gust: i32,
+    /// Humidity at noon for the day, or at midnight for the night
+    screen_relative_humidity: i32,
+    /// Day maximum or night minimum, depending on the period
+    temperature: i32,
+    visibility: Visibility,
+    wind_direction: Direction,
+    wind_speed: i32,
+    /// Only available for the day period
+    max_uv_index: Option<UvIndex>,
+    weather_type: WeatherType,
+    precipitation_probability: i32,
+}
+
+impl TryFrom<(i32, &str, &DailyRep)> for DailyForecast {
+    type Error = ForecastConversionError;
+
+    fn try_from(
+        (location_id, date_str, weather): (i32, &str, &DailyRep),
+    ) -> Result<Self, Self::Error> {
+        let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%dZ")
+            .map_err(|_| ForecastConversionError::DateTimeParseError(date_str.to_string()))?;
+        match weather {
+            DailyRep::Day(day) => DailyForecast::from_day(location_id, date, day),
+            DailyRep::Night(night) => DailyForecast::from_night(location_id, date, night),
+        }
+    }
+}
+
+impl DailyForecast {
+    fn from_day(
+        location_id: i32,
+        date: NaiveDate,
+        weather: &DayRep,
+    ) -> Result<Self, ForecastConversionError> {
+        Ok(Self {
+            location_id,
+            date,
+            period: DayPeriod::Day,
+            feels_like_temperature: weather.f_dm.parse()?,
+            wind_gust: weather.gn.parse()?,
+            screen_relative_humidity: weather.hn.parse()?,
+            temperature: weather.dm.parse()?,
+            visibility: Visibility::from_str(&weather.v)?,
+            wind_direction: Direction::from_str(&weather.d)?,
+            wind_speed: weather.s.parse()?,
+            max_uv_index: Some(UvIndex::from_str(&weather.u)?),
+            weather_type: WeatherType::from_str(&weather.w)?,
+            precipitation_probability: weather.pp_d.parse()?,
+        })
+    }
+
+    fn from_night(
+        location_id: i32,
+        date: NaiveDate,
+        weather: &NightRep,
+    ) -> Result<Self, ForecastConversionError> {
+        Ok(Self {
+            location_id,
+            date,
+            period: DayPeriod::Night,
+            feels_like_temperature: weather.f_nm.parse()?,
+            wind_gust: weather.gm.parse()?,
+            screen_relative_humidity: weather.hm.parse()?,
+            temperature: weather.nm.parse()?,
+            visibility: Visibility::from_str(&weather.v)?,
+            wind_direction: Direction::from_str(&weather.d)?,
+            wind_speed: weather.s.parse()?,
+            max_uv_index: None,
+            weather_type: WeatherType::from_str(&weather.w)?,
+            precipitation_probability: weather.pp_n.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::met_office::forecast_response::ForecastResponse;
+    use crate::met_office::DailyForecasts;
+    use std::convert::TryInto;
+
+    fn fixture() -> DailyForecasts {
+        let response: ForecastResponse<DailyRep> =
+            serde_json::from_str(include_str!("../../fixtures/daily_forecast.json")).unwrap();
+        response.try_into().unwrap()
+    }
+
+    #[test]
+    fn test_day_and_night_periods() {
+        let forecasts = fixture();
+        assert_eq!(forecasts.len(), 4);
+
+        let day = &forecasts[0];
+        assert_eq!(day.location_id, 310069);
+        assert_eq!(day.date, NaiveDate::from_ymd(2020, 10, 17));
+        assert_eq!(day.period, DayPeriod::Day);
+        assert_eq!(day.temperature, 14);
+        assert_eq!(day.feels_like_temperature, 11);
+        assert_eq!(day.wind_gust, 16);
+        assert_eq!(day.precipitation_probability, 5);
+        assert_eq!(
+            day.max_uv_index.as_ref().unwrap(),
+            &UvIndex::from_str("2").unwrap()
+        );
+
+        let night = &forecasts[1];
+        assert_eq!(night.date, NaiveDate::from_ymd(2020, 10, 17));
+        assert_eq!(night.period, DayPeriod::Night);
+        assert_eq!(night.temperature, 6);
+        assert_eq!(night.feels_like_temperature, 3);
+        assert_eq!(night.screen_relative_humidity, 89);
+        assert_eq!(night.weather_type, WeatherType::PartlyCloudyNight);
+        assert_eq!(night.max_uv_index, None);
+    }
+
+    #[test]
+    fn test_invalid_date() {
+        let rep: DailyRep = serde_json::from_str(
+            r#"{"D": "N", "Gm": "13", "Hm": "89", "PPn": "4", "S": "7", "V": "GO", "Nm": "6", "FNm": "3", "W": "2", "$": "Night"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            DailyForecast::try_from((1, "not a date", &rep)),
+            Err(ForecastConversionError::DateTimeParseError(_))
+        ));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::met_office::forecast_response::{DailyRep, ForecastLocation, ForecastResponse};
+use crate::met_office::{DailyForecast, ForecastConversionError};
+use std::convert::{TryFrom, TryInto};
+use std::ops::{Deref, DerefMut};
+
+#[derive(Clone)]
+pub struct DailyForecasts(pub Vec<DailyForecast>);
+
+impl Deref for DailyForecasts {
+    type Target = Vec<DailyForecast>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for DailyForecasts {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl TryFrom<&ForecastLocation<DailyRep>> for DailyForecasts {
+    type Error = ForecastConversionError;
+
+    fn try_from(location: &ForecastLocation<DailyRep>) -> Result<Self, Self::Error> {
+        let location_id = location.i.parse()?;
+        let forecasts: std::result::Result<Vec<_>, ForecastConversionError> = location
+            .period
+            .iter()
+            .flat_map(|period| {
+                let date = period.value.as_str();
+                period
+                    .rep
+                    .iter()
+                    .map(move |rep| DailyForecast::try_from((location_id, date, rep)))
+            })
+            .collect();
+        Ok(Self(forecasts?))
+    }
+}
+
+impl TryFrom<ForecastResponse<DailyRep>> for DailyForecasts {
+    type Error = ForecastConversionError;
+
+    fn try_from(value: ForecastResponse<DailyRep>) -> Result<Self, Self::Error> {
+        let mut forecasts = Vec::new();
+        for location in value.site_rep.dv.location.iter() {
+            let location_forecasts: DailyForecasts = location.try_into()?;
+            forecasts.extend(location_forecasts.0);
+        }
+        Ok(Self(forecasts))
+    }
+}
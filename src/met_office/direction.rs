@@ -3,7 +3,7 @@ use serde::Serialize;
 use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(Debug, PartialEq, Serialize, GraphQLEnum)]
+#[derive(Clone, Debug, PartialEq, Serialize, GraphQLEnum)]
 pub enum Direction {
     North,
     NorthNorthEast,
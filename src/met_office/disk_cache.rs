@@ -0,0 +1,233 @@
+//! Keeps DataPoint responses on disk so a restarted server starts warm
+//!
+//! Each response is a JSON file named after its hex encoded request path, with when it was fetched
+//! and when it expires. Files are written and deleted on a background thread so requests don't
+//! wait for the disk. Failures are only logged, the cache is best effort.
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StoredResponse {
+    pub key: String,
+    pub body: String,
+    /// Seconds since the unix epoch
+    pub fetched_at: u64,
+    pub expires_at: u64,
+}
+
+impl StoredResponse {
+    pub fn fetched_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.fetched_at)
+    }
+
+    pub fn expires_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.expires_at)
+    }
+
+    /// Whether it expired less than `max_stale` before `now`, a `max_stale` too long to add to the
+    /// expiry never runs out
+    fn is_servable(&self, max_stale: Duration, now: SystemTime) -> bool {
+        match self.expires_at().checked_add(max_stale) {
+            Some(servable_until) => servable_until > now,
+            None => true,
+        }
+    }
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn file_name(key: &str) -> String {
+    let hex: String = key.bytes().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}.json", hex)
+}
+
+enum DiskWrite {
+    Store(StoredResponse),
+    Remove(String),
+}
+
+fn write(dir: &Path, write: DiskWrite) {
+    match write {
+        DiskWrite::Store(stored) => {
+            let path = dir.join(file_name(&stored.key));
+            let written = serde_json::to_vec(&stored)
+                .map_err(|error| error.to_string())
+                .and_then(|json| fs::write(&path, json).map_err(|error| error.to_string()));
+            if let Err(error) = written {
+                warn!("could not write {:?}: {}", path, error);
+            }
+        }
+        DiskWrite::Remove(key) => {
+            let path = dir.join(file_name(&key));
+            match fs::remove_file(&path) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => {
+                    warn!("could not delete {:?}: {}", path, error)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Dropping it waits for the writes that are still queued
+pub struct DiskCache {
+    dir: PathBuf,
+    writes: Option<Sender<DiskWrite>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf) -> DiskCache {
+        if let Err(error) = fs::create_dir_all(&dir) {
+            warn!("could not create the cache directory {:?}: {}", dir, error);
+        }
+        let (writes, queued) = mpsc::channel();
+        let writer_dir = dir.clone();
+        let writer = thread::Builder::new()
+            .name("disk-cache".to_string())
+            .spawn(move || {
+                queued
+                    .into_iter()
+                    .for_each(|queued| write(&writer_dir, queued))
+            });
+        let writer = match writer {
+            Ok(writer) => Some(writer),
+            Err(error) => {
+                warn!("could not start the disk cache writer: {}", error);
+                None
+            }
+        };
+        DiskCache {
+            dir,
+            writes: Some(writes),
+            writer,
+        }
+    }
+
+    fn queue(&self, write: DiskWrite) {
+        if let Some(writes) = &self.writes {
+            let _ = writes.send(write);
+        }
+    }
+
+    pub fn store(&self, key: &str, body: &str, fetched_at: SystemTime, expires_at: SystemTime) {
+        self.queue(DiskWrite::Store(StoredResponse {
+            key: key.to_string(),
+            body: body.to_string(),
+            fetched_at: seconds(fetched_at),
+            expires_at: seconds(expires_at),
+        }));
+    }
+
+    /// Deletes a response that is no longer cached
+    pub fn remove(&self, key: &str) {
+        self.queue(DiskWrite::Remove(key.to_string()));
+    }
+
+    /// Reads the responses that expired less than `max_stale` ago and deletes the rest
+    pub fn load(&self, now: SystemTime, max_stale: Duration) -> Vec<StoredResponse> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                warn!(
+                    "could not read the cache directory {:?}: {}",
+                    self.dir, error
+                );
+                return Vec::new();
+            }
+        };
+        let mut responses = Vec::new();
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            let stored = fs::read(&path)
+                .ok()
+                .and_then(|json| serde_json::from_slice::<StoredResponse>(&json).ok());
+            match stored {
+                Some(stored) if stored.is_servable(max_stale, now) => responses.push(stored),
+                _ => {
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+        if let Some(oldest) = responses.iter().map(StoredResponse::fetched_at).min() {
+            info!(
+                "Loaded {} cached DataPoint responses from {:?}, the oldest is {} minutes old",
+                responses.len(),
+                self.dir,
+                now.duration_since(oldest).unwrap_or_default().as_secs() / 60
+            );
+        }
+        responses
+    }
+}
+
+impl Drop for DiskCache {
+    fn drop(&mut self) {
+        self.writes.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("weather-api-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let dir = cache_dir("store");
+        let now = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let hour = Duration::from_secs(60 * 60);
+        {
+            let cache = DiskCache::new(dir.clone());
+            cache.store("val/wxfcs/all/json/sitelist", "{}", now, now + hour);
+            cache.store("val/wxfcs/all/json/3840?res=daily", "[]", now - hour, now);
+            cache.store("val/wxfcs/all/json/3840?res=3hourly", "[]", now, now + hour);
+            cache.remove("val/wxfcs/all/json/3840?res=3hourly");
+            cache.remove("val/wxfcs/all/json/310069?res=3hourly");
+        }
+
+        let loaded = DiskCache::new(dir.clone()).load(now, Duration::from_secs(0));
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].key, "val/wxfcs/all/json/sitelist");
+        assert_eq!(loaded[0].body, "{}");
+        assert_eq!(loaded[0].fetched_at(), now);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_load_with_max_stale_too_long_to_add() {
+        let dir = cache_dir("max-stale");
+        let now = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        DiskCache::new(dir.clone()).store("a", "{}", now, now);
+
+        let loaded = DiskCache::new(dir.clone()).load(now, Duration::from_secs(u64::MAX));
+        assert_eq!(loaded.len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_file_names_are_safe() {
+        assert_eq!(file_name("a/b?c"), "612f623f63.json");
+    }
+}
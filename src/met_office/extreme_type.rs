@@ -0,0 +1,83 @@
+use juniper::GraphQLEnum;
+use serde::Serialize;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Eq, PartialEq, GraphQLEnum, Serialize)]
+pub enum ExtremeType {
+    HighestMaximumTemperature,
+    LowestMaximumTemperature,
+    HighestMinimumTemperature,
+    LowestMinimumTemperature,
+    HighestRainfall,
+    HighestSunshine,
+    HighestGust,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ExtremeTypeConversionError {
+    #[error("invalid extreme type code, found {0}")]
+    InvalidCode(String),
+}
+
+impl FromStr for ExtremeType {
+    type Err = ExtremeTypeConversionError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        match code {
+            "HMAXT" => Ok(ExtremeType::HighestMaximumTemperature),
+            "LMAXT" => Ok(ExtremeType::LowestMaximumTemperature),
+            "HMINT" => Ok(ExtremeType::HighestMinimumTemperature),
+            "LMINT" => Ok(ExtremeType::LowestMinimumTemperature),
+            "HRAIN" => Ok(ExtremeType::HighestRainfall),
+            "HSUN" => Ok(ExtremeType::HighestSunshine),
+            "HGUST" => Ok(ExtremeType::HighestGust),
+            _ => Err(ExtremeTypeConversionError::InvalidCode(code.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_valid_extreme_type_codes() {
+        assert_eq!(
+            ExtremeType::from_str("HMAXT").unwrap(),
+            ExtremeType::HighestMaximumTemperature
+        );
+        assert_eq!(
+            ExtremeType::from_str("LMAXT").unwrap(),
+            ExtremeType::LowestMaximumTemperature
+        );
+        assert_eq!(
+            ExtremeType::from_str("HMINT").unwrap(),
+            ExtremeType::HighestMinimumTemperature
+        );
+        assert_eq!(
+            ExtremeType::from_str("LMINT").unwrap(),
+            ExtremeType::LowestMinimumTemperature
+        );
+        assert_eq!(
+            ExtremeType::from_str("HRAIN").unwrap(),
+            ExtremeType::HighestRainfall
+        );
+        assert_eq!(
+            ExtremeType::from_str("HSUN").unwrap(),
+            ExtremeType::HighestSunshine
+        );
+        assert_eq!(
+            ExtremeType::from_str("HGUST").unwrap(),
+            ExtremeType::HighestGust
+        );
+    }
+
+    #[test]
+    fn test_unknown_code() {
+        assert_eq!(
+            ExtremeType::from_str("An invalid code").unwrap_err(),
+            ExtremeTypeConversionError::InvalidCode("An invalid code".to_string())
+        );
+    }
+}
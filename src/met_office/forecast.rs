@@ -4,6 +4,7 @@ use crate::met_office::{
     uv::{UvCodeConversionError, UvIndex},
     visibility::{Visibility, VisibilityCodeConversionError},
     weather_type::{WeatherType, WeatherTypeCodeConversionError},
+    Freshness,
 };
 use chrono::{Duration, NaiveDateTime};
 use juniper::GraphQLObject;
@@ -13,7 +14,7 @@ use std::ops::Add;
 use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(GraphQLObject, Serialize)]
+#[derive(Clone, GraphQLObject, Serialize)]
 pub struct Forecast {
     location_id: i32,
     timestamp: NaiveDateTime,
@@ -27,9 +28,25 @@ pub struct Forecast {
     max_uv_index: UvIndex,
     weather_type: WeatherType,
     precipitation_probability: i32,
+    /// Whether DataPoint was unavailable and this is the last forecast fetched from it
+    stale: bool,
+    /// When the forecast was fetched from DataPoint
+    fetched_at: Option<NaiveDateTime>,
+}
+
+impl Forecast {
+    pub fn set_freshness(&mut self, freshness: Freshness) {
+        self.stale = freshness.stale;
+        self.fetched_at = Some(freshness.fetched_at);
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
 }
 
 #[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum ForecastConversionError {
     #[error("Direction Conversion Error: {0:?}")]
     DirectionConversionError(#[from] DirectionConversionError),
@@ -68,6 +85,8 @@ impl TryFrom<(i32, &str, &Rep)> for Forecast {
             max_uv_index: UvIndex::from_str(&weather.u)?,
             weather_type: WeatherType::from_str(&weather.w)?,
             precipitation_probability: weather.pp.parse()?,
+            stale: false,
+            fetched_at: None,
         })
     }
 }
@@ -1,17 +1,36 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+
+/// DataPoint returns a single object rather than a list when there is only one item
+pub fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(item) => vec![item],
+        OneOrMany::Many(items) => items,
+    })
+}
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
-pub struct ForecastResponse {
-    pub site_rep: SiteRep,
+pub struct ForecastResponse<R = Rep> {
+    pub site_rep: SiteRep<R>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct SiteRep {
+pub struct SiteRep<R> {
     #[serde(rename = "Wx")]
     pub wx: Params,
     #[serde(rename = "DV")]
-    pub dv: Dv,
+    pub dv: Dv<R>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,35 +48,38 @@ pub struct Param {
 }
 
 #[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-pub struct Dv {
+#[serde(rename_all = "PascalCase", bound(deserialize = "R: Deserialize<'de>"))]
+pub struct Dv<R> {
     #[serde(rename = "dataDate")]
     pub data_date: String,
     #[serde(rename = "type")]
     pub data_type: String,
-    #[serde(rename = "Location")]
-    pub location: ForecastLocation,
+    #[serde(rename = "Location", deserialize_with = "one_or_many")]
+    pub location: Vec<ForecastLocation<R>>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct ForecastLocation {
+#[serde(bound(deserialize = "R: Deserialize<'de>"))]
+pub struct ForecastLocation<R> {
     pub i: String,
     pub lat: String,
     pub lon: String,
     pub name: String,
     pub country: String,
     pub continent: String,
-    #[serde(rename = "Period")]
-    pub period: Vec<ForecastPeriod>,
+    pub elevation: Option<String>,
+    #[serde(rename = "Period", deserialize_with = "one_or_many")]
+    pub period: Vec<ForecastPeriod<R>>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct ForecastPeriod {
+#[serde(bound(deserialize = "R: Deserialize<'de>"))]
+pub struct ForecastPeriod<R> {
     #[serde(rename = "type")]
     pub period_type: String,
     pub value: String,
-    #[serde(rename = "Rep")]
-    pub rep: Vec<Rep>,
+    #[serde(rename = "Rep", deserialize_with = "one_or_many")]
+    pub rep: Vec<R>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -76,3 +98,81 @@ pub struct Rep {
     pub v: String,
     pub f: String,
 }
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "$")]
+pub enum DailyRep {
+    Day(DayRep),
+    Night(NightRep),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DayRep {
+    pub d: String,
+    pub gn: String,
+    pub hn: String,
+    #[serde(rename = "PPd")]
+    pub pp_d: String,
+    pub s: String,
+    pub v: String,
+    pub dm: String,
+    #[serde(rename = "FDm")]
+    pub f_dm: String,
+    pub w: String,
+    pub u: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct NightRep {
+    pub d: String,
+    pub gm: String,
+    pub hm: String,
+    #[serde(rename = "PPn")]
+    pub pp_n: String,
+    pub s: String,
+    pub v: String,
+    pub nm: String,
+    #[serde(rename = "FNm")]
+    pub f_nm: String,
+    pub w: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ObservationRep {
+    pub g: Option<String>,
+    pub t: Option<String>,
+    pub v: Option<String>,
+    pub d: Option<String>,
+    pub s: Option<String>,
+    pub w: Option<String>,
+    pub p: Option<String>,
+    pub pt: Option<String>,
+    pub dp: Option<String>,
+    pub h: Option<String>,
+    #[serde(rename = "$")]
+    pub dollar: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_time_step() {
+        let response: ForecastResponse = serde_json::from_str(
+            r#"{"SiteRep": {"Wx": {"Param": []}, "DV": {"dataDate": "2020-10-17T15:00:00Z", "type": "Forecast",
+                "Location": {"i": "3002", "lat": "60.749", "lon": "-0.854", "name": "BALTASOUND", "country": "SCOTLAND", "continent": "EUROPE",
+                    "Period": {"type": "Day", "value": "2020-10-17Z",
+                        "Rep": {"D": "SSW", "F": "6", "G": "27", "H": "81", "Pp": "8", "S": "18", "T": "9", "V": "VG", "W": "7", "U": "0", "$": "900"}}}}}}"#,
+        )
+        .unwrap();
+        let location = &response.site_rep.dv.location;
+        assert_eq!(location.len(), 1);
+        assert_eq!(location[0].period.len(), 1);
+        assert_eq!(location[0].period[0].rep.len(), 1);
+        assert_eq!(location[0].period[0].rep[0].dollar, "900");
+    }
+}
@@ -1,10 +1,23 @@
-use crate::met_office::forecast_response::ForecastResponse;
-use crate::met_office::{Forecast, ForecastConversionError};
-use std::convert::TryFrom;
+use crate::met_office::forecast_response::{ForecastLocation, ForecastResponse, Rep};
+use crate::met_office::{Forecast, ForecastConversionError, Freshness};
+use std::convert::{TryFrom, TryInto};
 use std::ops::{Deref, DerefMut};
 
+#[derive(Clone)]
 pub struct Forecasts(pub Vec<Forecast>);
 
+impl Forecasts {
+    pub fn set_freshness(&mut self, freshness: Freshness) {
+        for forecast in &mut self.0 {
+            forecast.set_freshness(freshness);
+        }
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.iter().any(Forecast::is_stale)
+    }
+}
+
 impl Deref for Forecasts {
     type Target = Vec<Forecast>;
 
@@ -19,11 +32,10 @@ impl DerefMut for Forecasts {
     }
 }
 
-impl TryFrom<ForecastResponse> for Forecasts {
+impl TryFrom<&ForecastLocation<Rep>> for Forecasts {
     type Error = ForecastConversionError;
 
-    fn try_from(value: ForecastResponse) -> Result<Self, Self::Error> {
-        let location = value.site_rep.dv.location;
+    fn try_from(location: &ForecastLocation<Rep>) -> Result<Self, Self::Error> {
         let location_id = location.i.parse()?;
         let forecasts: std::result::Result<Vec<_>, ForecastConversionError> = location
             .period
@@ -39,3 +51,16 @@ impl TryFrom<ForecastResponse> for Forecasts {
         Ok(Self(forecasts?))
     }
 }
+
+impl TryFrom<ForecastResponse> for Forecasts {
+    type Error = ForecastConversionError;
+
+    fn try_from(value: ForecastResponse) -> Result<Self, Self::Error> {
+        let mut forecasts = Vec::new();
+        for location in value.site_rep.dv.location.iter() {
+            let location_forecasts: Forecasts = location.try_into()?;
+            forecasts.extend(location_forecasts.0);
+        }
+        Ok(Self(forecasts))
+    }
+}
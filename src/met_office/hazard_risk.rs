@@ -0,0 +1,62 @@
+use juniper::GraphQLEnum;
+use serde::Serialize;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Eq, PartialEq, GraphQLEnum, Serialize)]
+pub enum HazardRisk {
+    VeryLow,
+    Low,
+    Medium,
+    High,
+    VeryHigh,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum HazardRiskConversionError {
+    #[error("invalid hazard risk, found {0}")]
+    InvalidRisk(String),
+}
+
+impl FromStr for HazardRisk {
+    type Err = HazardRiskConversionError;
+
+    fn from_str(risk: &str) -> Result<Self, Self::Err> {
+        match risk.to_lowercase().as_str() {
+            "very low" => Ok(HazardRisk::VeryLow),
+            "low" => Ok(HazardRisk::Low),
+            "medium" => Ok(HazardRisk::Medium),
+            "high" => Ok(HazardRisk::High),
+            "very high" => Ok(HazardRisk::VeryHigh),
+            _ => Err(HazardRiskConversionError::InvalidRisk(risk.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_valid_hazard_risks() {
+        assert_eq!(
+            HazardRisk::from_str("Very low").unwrap(),
+            HazardRisk::VeryLow
+        );
+        assert_eq!(HazardRisk::from_str("Low").unwrap(), HazardRisk::Low);
+        assert_eq!(HazardRisk::from_str("Medium").unwrap(), HazardRisk::Medium);
+        assert_eq!(HazardRisk::from_str("High").unwrap(), HazardRisk::High);
+        assert_eq!(
+            HazardRisk::from_str("Very High").unwrap(),
+            HazardRisk::VeryHigh
+        );
+    }
+
+    #[test]
+    fn test_unknown_risk() {
+        assert_eq!(
+            HazardRisk::from_str("An invalid risk").unwrap_err(),
+            HazardRiskConversionError::InvalidRisk("An invalid risk".to_string())
+        );
+    }
+}
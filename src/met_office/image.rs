@@ -0,0 +1,6 @@
+/// An image fetched from DataPoint, kept as raw bytes so it can be cached and re-served
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
@@ -1,9 +1,11 @@
+use crate::met_office::Freshness;
+use chrono::NaiveDateTime;
 use core::fmt;
 use serde::Deserialize;
 use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LocationId {
     All,
     Location(u32),
@@ -39,7 +41,7 @@ impl FromStr for LocationId {
     }
 }
 
-#[derive(Debug, Deserialize, juniper::GraphQLObject)]
+#[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct Location {
     pub id: String,
@@ -49,6 +51,18 @@ pub struct Location {
     pub elevation: Option<String>,
     pub region: Option<String>,
     pub unitary_auth_area: Option<String>,
+    /// Set once the site list has been fetched, see `MetApi::forecast_site_list`
+    #[serde(skip)]
+    pub stale: bool,
+    #[serde(skip)]
+    pub fetched_at: Option<NaiveDateTime>,
+}
+
+impl Location {
+    pub fn set_freshness(&mut self, freshness: Freshness) {
+        self.stale = freshness.stale;
+        self.fetched_at = Some(freshness.fetched_at);
+    }
 }
 
 #[derive(Deserialize)]
@@ -0,0 +1,83 @@
+use crate::met_office::forecast_response::{ForecastLocation, ForecastResponse, Rep};
+use crate::met_office::{ForecastConversionError, Forecasts, Freshness, Location, LocationId};
+use std::convert::{TryFrom, TryInto};
+
+/// The forecasts for a single site, as returned when requesting every site at once
+#[derive(Clone)]
+pub struct LocationForecasts {
+    pub location_id: LocationId,
+    pub location: Location,
+    pub forecasts: Forecasts,
+}
+
+impl LocationForecasts {
+    pub fn set_freshness(&mut self, freshness: Freshness) {
+        self.location.set_freshness(freshness);
+        self.forecasts.set_freshness(freshness);
+    }
+}
+
+impl TryFrom<&ForecastLocation<Rep>> for LocationForecasts {
+    type Error = ForecastConversionError;
+
+    fn try_from(location: &ForecastLocation<Rep>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            location_id: LocationId::Location(location.i.parse()?),
+            location: Location {
+                id: location.i.clone(),
+                name: location.name.clone(),
+                latitude: location.lat.clone(),
+                longitude: location.lon.clone(),
+                elevation: location.elevation.clone(),
+                region: None,
+                unitary_auth_area: None,
+                stale: false,
+                fetched_at: None,
+            },
+            forecasts: location.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<ForecastResponse> for Vec<LocationForecasts> {
+    type Error = ForecastConversionError;
+
+    fn try_from(value: ForecastResponse) -> Result<Self, Self::Error> {
+        value
+            .site_rep
+            .dv
+            .location
+            .iter()
+            .map(LocationForecasts::try_from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_sites() {
+        let response: ForecastResponse =
+            serde_json::from_str(include_str!("../../fixtures/forecast_all.json")).unwrap();
+        let groups: Vec<LocationForecasts> = response.try_into().unwrap();
+        assert_eq!(groups.len(), 2);
+
+        assert_eq!(groups[0].location_id, LocationId::Location(3002));
+        assert_eq!(groups[0].location.name, "BALTASOUND");
+        assert_eq!(groups[0].forecasts.len(), 2);
+
+        assert_eq!(groups[1].location_id, LocationId::Location(310069));
+        assert_eq!(groups[1].location.elevation, Some("27.0".to_string()));
+        assert_eq!(groups[1].forecasts.len(), 1);
+    }
+
+    #[test]
+    fn test_all_sites_flattened() {
+        let response: ForecastResponse =
+            serde_json::from_str(include_str!("../../fixtures/forecast_all.json")).unwrap();
+        let forecasts: Forecasts = response.try_into().unwrap();
+        assert_eq!(forecasts.len(), 3);
+    }
+}
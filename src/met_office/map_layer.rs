@@ -0,0 +1,261 @@
+use crate::met_office::capabilities::{format_time_step, parse_time_step};
+use crate::met_office::forecast_response::one_or_many;
+use crate::met_office::text_forecast::parse_issue_time;
+use crate::met_office::ForecastConversionError;
+use chrono::{Duration, NaiveDateTime};
+use juniper::GraphQLObject;
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+/// Where the proxied layer images are served from, see `image_proxy`
+pub const LAYER_PROXY_PATH: &str = "/layers";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LayersResponse {
+    pub layers: Layers,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Layers {
+    #[serde(deserialize_with = "one_or_many")]
+    pub layer: Vec<Layer>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Layer {
+    #[serde(rename = "@displayName")]
+    pub display_name: String,
+    #[serde(rename = "Service")]
+    pub service: Service,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Service {
+    pub layer_name: String,
+    pub image_format: String,
+    pub timesteps: Option<Timesteps>,
+    pub times: Option<Times>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Timesteps {
+    #[serde(rename = "@defaultTime")]
+    pub default_time: String,
+    #[serde(rename = "Timestep", deserialize_with = "one_or_many")]
+    pub timestep: Vec<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Times {
+    #[serde(rename = "Time", deserialize_with = "one_or_many")]
+    pub time: Vec<String>,
+}
+
+/// A single image from a layer, either a forecast run and step or an observation time
+#[derive(Debug, PartialEq)]
+pub enum LayerRequest {
+    Forecast { run: NaiveDateTime, step: u32 },
+    Observation { time: NaiveDateTime },
+}
+
+impl LayerRequest {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LayerRequest::Forecast { .. } => "wxfcs",
+            LayerRequest::Observation { .. } => "wxobs",
+        }
+    }
+
+    pub fn query(&self) -> String {
+        match self {
+            LayerRequest::Forecast { run, step } => {
+                format!("RUN={}&FORECAST={}", format_time_step(*run), step)
+            }
+            LayerRequest::Observation { time } => format!("TIME={}", format_time_step(*time)),
+        }
+    }
+
+    /// Builds the request from the proxy route's path and query parameters
+    pub fn from_parts(
+        kind: &str,
+        run: Option<&str>,
+        forecast: Option<u32>,
+        time: Option<&str>,
+    ) -> Option<LayerRequest> {
+        match (kind, run, forecast, time) {
+            ("wxfcs", Some(run), Some(step), _) => Some(LayerRequest::Forecast {
+                run: parse_time_step(run).ok()?,
+                step,
+            }),
+            ("wxobs", _, _, Some(time)) => Some(LayerRequest::Observation {
+                time: parse_time_step(time).ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Layer and image format names end up in the upstream path so only allow plain names
+pub fn is_valid_layer_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[derive(GraphQLObject)]
+pub struct MapLayer {
+    display_name: String,
+    layer_name: String,
+    image_format: String,
+    images: Vec<LayerImage>,
+}
+
+#[derive(GraphQLObject)]
+pub struct LayerImage {
+    /// The time the image is valid for
+    time: NaiveDateTime,
+    /// Path of the image on this server, the API key is added by the server
+    url: String,
+}
+
+impl LayerImage {
+    fn new(
+        layer_name: &str,
+        image_format: &str,
+        time: NaiveDateTime,
+        request: LayerRequest,
+    ) -> Self {
+        Self {
+            time,
+            url: format!(
+                "{}/{}/{}/{}?{}",
+                LAYER_PROXY_PATH,
+                request.kind(),
+                layer_name,
+                image_format,
+                request.query()
+            ),
+        }
+    }
+}
+
+impl TryFrom<Layer> for MapLayer {
+    type Error = ForecastConversionError;
+
+    fn try_from(layer: Layer) -> Result<Self, Self::Error> {
+        let service = layer.service;
+        let mut images = Vec::new();
+        if let Some(timesteps) = &service.timesteps {
+            let run = parse_issue_time(&timesteps.default_time)?;
+            for step in timesteps.timestep.iter() {
+                images.push(LayerImage::new(
+                    &service.layer_name,
+                    &service.image_format,
+                    run + Duration::hours(i64::from(*step)),
+                    LayerRequest::Forecast { run, step: *step },
+                ));
+            }
+        }
+        if let Some(times) = &service.times {
+            for time in times.time.iter() {
+                let time = parse_issue_time(time)?;
+                images.push(LayerImage::new(
+                    &service.layer_name,
+                    &service.image_format,
+                    time,
+                    LayerRequest::Observation { time },
+                ));
+            }
+        }
+        Ok(Self {
+            display_name: layer.display_name,
+            layer_name: service.layer_name,
+            image_format: service.image_format,
+            images,
+        })
+    }
+}
+
+impl TryFrom<LayersResponse> for Vec<MapLayer> {
+    type Error = ForecastConversionError;
+
+    fn try_from(value: LayersResponse) -> Result<Self, Self::Error> {
+        value
+            .layers
+            .layer
+            .into_iter()
+            .map(MapLayer::try_from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_forecast_layers() {
+        let response: LayersResponse =
+            serde_json::from_str(include_str!("../../fixtures/forecast_layers.json")).unwrap();
+        let layers: Vec<MapLayer> = response.try_into().unwrap();
+        assert_eq!(layers.len(), 2);
+
+        let rainfall = &layers[0];
+        assert_eq!(rainfall.display_name, "Rainfall");
+        assert_eq!(rainfall.layer_name, "Precipitation_Rate");
+        assert_eq!(rainfall.images.len(), 3);
+        assert_eq!(
+            rainfall.images[1].time,
+            parse_issue_time("2020-10-17T15:00:00").unwrap()
+        );
+        assert_eq!(
+            rainfall.images[1].url,
+            "/layers/wxfcs/Precipitation_Rate/png?RUN=2020-10-17T12:00:00Z&FORECAST=3"
+        );
+    }
+
+    #[test]
+    fn test_observation_layers() {
+        let response: LayersResponse =
+            serde_json::from_str(include_str!("../../fixtures/observation_layers.json")).unwrap();
+        let layers: Vec<MapLayer> = response.try_into().unwrap();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].images.len(), 2);
+        assert_eq!(
+            layers[0].images[0].url,
+            "/layers/wxobs/RADAR_UK_Composite_Highres/png?TIME=2020-10-17T15:00:00Z"
+        );
+    }
+
+    #[test]
+    fn test_layer_request_from_parts() {
+        assert_eq!(
+            LayerRequest::from_parts("wxfcs", Some("2020-10-17T12:00:00Z"), Some(3), None),
+            Some(LayerRequest::Forecast {
+                run: parse_issue_time("2020-10-17T12:00:00").unwrap(),
+                step: 3
+            })
+        );
+        assert_eq!(
+            LayerRequest::from_parts("wxobs", None, None, Some("2020-10-17T15:00:00Z")),
+            Some(LayerRequest::Observation {
+                time: parse_issue_time("2020-10-17T15:00:00").unwrap()
+            })
+        );
+        assert_eq!(LayerRequest::from_parts("wxfcs", None, Some(3), None), None);
+        assert_eq!(
+            LayerRequest::from_parts("other", None, None, Some("2020-10-17T15:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_layer_names() {
+        assert!(is_valid_layer_name("Precipitation_Rate"));
+        assert!(is_valid_layer_name("png"));
+        assert!(!is_valid_layer_name(""));
+        assert!(!is_valid_layer_name("../sitelist"));
+    }
+}
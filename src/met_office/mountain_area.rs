@@ -0,0 +1,241 @@
+use crate::met_office::forecast_response::one_or_many;
+use crate::met_office::hazard_risk::{HazardRisk, HazardRiskConversionError};
+use crate::met_office::text_forecast::parse_issue_time;
+use chrono::{NaiveDate, NaiveDateTime};
+use juniper::GraphQLObject;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize, GraphQLObject)]
+pub struct MountainArea {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "@name")]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MountainAreaForecastResponse {
+    pub report: Report,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Report {
+    pub location: String,
+    pub issued: String,
+    #[serde(rename = "ValidFrom")]
+    pub valid_from: String,
+    #[serde(rename = "ValidTo")]
+    pub valid_to: String,
+    #[serde(rename = "Overview")]
+    pub overview: String,
+    #[serde(rename = "Hazards")]
+    pub hazards: Hazards,
+    #[serde(rename = "Days")]
+    pub days: Days,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Hazards {
+    #[serde(rename = "Hazard", deserialize_with = "one_or_many", default)]
+    pub hazard: Vec<HazardReport>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct HazardReport {
+    pub element: String,
+    pub risk: String,
+    pub comments: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Days {
+    #[serde(rename = "Day", deserialize_with = "one_or_many")]
+    pub day: Vec<DayReport>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DayReport {
+    #[serde(rename = "date")]
+    pub date: String,
+    pub weather: String,
+    pub visibility: String,
+    pub hill_fog: String,
+    pub max_wind_level: String,
+    pub max_wind: String,
+    pub temp_low_level: String,
+    pub temp_high_level: String,
+    pub freezing_level: String,
+}
+
+#[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
+pub enum MountainForecastConversionError {
+    #[error("Hazard Risk Conversion Error: {0:?}")]
+    HazardRiskConversionError(#[from] HazardRiskConversionError),
+    #[error("Could not parse the date: {0}")]
+    DateTimeParseError(String),
+}
+
+#[derive(GraphQLObject)]
+pub struct MountainForecast {
+    location: String,
+    issued_at: NaiveDateTime,
+    valid_from: NaiveDateTime,
+    valid_to: NaiveDateTime,
+    overview: String,
+    hazards: Vec<Hazard>,
+    days: Vec<MountainForecastDay>,
+}
+
+#[derive(GraphQLObject)]
+pub struct Hazard {
+    element: String,
+    risk: HazardRisk,
+    comments: String,
+}
+
+#[derive(GraphQLObject)]
+pub struct MountainForecastDay {
+    date: NaiveDate,
+    weather: String,
+    visibility: String,
+    hill_fog: String,
+    /// The height the summit wind applies to
+    summit_wind_level: String,
+    summit_wind: String,
+    temperature_low_level: String,
+    temperature_high_level: String,
+    freezing_level: String,
+}
+
+fn parse_time(time: &str) -> Result<NaiveDateTime, MountainForecastConversionError> {
+    parse_issue_time(time)
+        .map_err(|_| MountainForecastConversionError::DateTimeParseError(time.to_string()))
+}
+
+impl TryFrom<HazardReport> for Hazard {
+    type Error = MountainForecastConversionError;
+
+    fn try_from(hazard: HazardReport) -> Result<Self, Self::Error> {
+        Ok(Self {
+            risk: HazardRisk::from_str(&hazard.risk)?,
+            element: hazard.element,
+            comments: hazard.comments,
+        })
+    }
+}
+
+impl TryFrom<DayReport> for MountainForecastDay {
+    type Error = MountainForecastConversionError;
+
+    fn try_from(day: DayReport) -> Result<Self, Self::Error> {
+        let date = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+            .map_err(|_| MountainForecastConversionError::DateTimeParseError(day.date.clone()))?;
+        Ok(Self {
+            date,
+            weather: day.weather,
+            visibility: day.visibility,
+            hill_fog: day.hill_fog,
+            summit_wind_level: day.max_wind_level,
+            summit_wind: day.max_wind,
+            temperature_low_level: day.temp_low_level,
+            temperature_high_level: day.temp_high_level,
+            freezing_level: day.freezing_level,
+        })
+    }
+}
+
+impl TryFrom<MountainAreaForecastResponse> for MountainForecast {
+    type Error = MountainForecastConversionError;
+
+    fn try_from(value: MountainAreaForecastResponse) -> Result<Self, Self::Error> {
+        let report = value.report;
+        let hazards: Result<Vec<_>, _> = report
+            .hazards
+            .hazard
+            .into_iter()
+            .map(Hazard::try_from)
+            .collect();
+        let days: Result<Vec<_>, _> = report
+            .days
+            .day
+            .into_iter()
+            .map(MountainForecastDay::try_from)
+            .collect();
+        Ok(Self {
+            location: report.location,
+            issued_at: parse_time(&report.issued)?,
+            valid_from: parse_time(&report.valid_from)?,
+            valid_to: parse_time(&report.valid_to)?,
+            overview: report.overview,
+            hazards: hazards?,
+            days: days?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::met_office::text_forecast::SitesResponse;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_mountain_areas() {
+        let response: SitesResponse<MountainArea> =
+            serde_json::from_str(include_str!("../../fixtures/mountain_area_sitelist.json"))
+                .unwrap();
+        let areas = response.locations.location;
+        assert_eq!(areas.len(), 3);
+        assert_eq!(areas[1].id, "101");
+        assert_eq!(areas[1].name, "Lake District");
+    }
+
+    #[test]
+    fn test_mountain_forecast() {
+        let response: MountainAreaForecastResponse =
+            serde_json::from_str(include_str!("../../fixtures/mountain_area_forecast.json"))
+                .unwrap();
+        let forecast: MountainForecast = response.try_into().unwrap();
+        assert_eq!(forecast.location, "Lake District");
+        assert_eq!(
+            forecast.valid_from,
+            parse_issue_time("2020-10-18T00:00:00").unwrap()
+        );
+
+        assert_eq!(forecast.hazards.len(), 2);
+        assert_eq!(forecast.hazards[0].element, "Strong winds");
+        assert_eq!(forecast.hazards[0].risk, HazardRisk::Medium);
+        assert_eq!(forecast.hazards[1].risk, HazardRisk::Low);
+
+        assert_eq!(forecast.days.len(), 2);
+        let today = &forecast.days[0];
+        assert_eq!(today.date, NaiveDate::from_ymd(2020, 10, 18));
+        assert_eq!(today.summit_wind_level, "900m");
+        assert_eq!(today.freezing_level, "Above the summits");
+        assert_eq!(today.visibility, "Very good");
+    }
+
+    #[test]
+    fn test_single_hazard_and_invalid_risk() {
+        let response: MountainAreaForecastResponse = serde_json::from_str(
+            r#"{"report": {"location": "Snowdonia", "issued": "2020-10-17T16:00:00",
+                "ValidFrom": "2020-10-18T00:00:00", "ValidTo": "2020-10-18T23:59:59", "Overview": "",
+                "Hazards": {"Hazard": {"Element": "Blizzards", "Risk": "Extreme", "Comments": ""}},
+                "Days": {"Day": []}}}"#,
+        )
+        .unwrap();
+        assert_eq!(response.report.hazards.hazard.len(), 1);
+        assert!(matches!(
+            MountainForecast::try_from(response),
+            Err(MountainForecastConversionError::HazardRiskConversionError(
+                _
+            ))
+        ));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::met_office::text_forecast::{parse_issue_time, FcstPeriods, TextForecastPeriod};
+use crate::met_office::ForecastConversionError;
+use chrono::NaiveDateTime;
+use juniper::GraphQLObject;
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+#[derive(Debug, Deserialize, GraphQLObject)]
+pub struct NationalPark {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "@name")]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct NationalParkForecastResponse {
+    pub national_park_fcst: NationalParkFcst,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NationalParkFcst {
+    #[serde(rename = "createdOn")]
+    pub created_on: String,
+    #[serde(rename = "issuedAt")]
+    pub issued_at: String,
+    #[serde(rename = "parkId")]
+    pub park_id: String,
+    #[serde(rename = "parkName")]
+    pub park_name: String,
+    #[serde(rename = "FcstPeriods")]
+    pub fcst_periods: FcstPeriods,
+}
+
+#[derive(GraphQLObject)]
+pub struct NationalParkForecast {
+    park: NationalPark,
+    created_on: NaiveDateTime,
+    issued_at: NaiveDateTime,
+    periods: Vec<TextForecastPeriod>,
+}
+
+impl TryFrom<NationalParkForecastResponse> for NationalParkForecast {
+    type Error = ForecastConversionError;
+
+    fn try_from(value: NationalParkForecastResponse) -> Result<Self, Self::Error> {
+        let forecast = value.national_park_fcst;
+        Ok(Self {
+            created_on: parse_issue_time(&forecast.created_on)?,
+            issued_at: parse_issue_time(&forecast.issued_at)?,
+            park: NationalPark {
+                id: forecast.park_id,
+                name: forecast.park_name,
+            },
+            periods: forecast.fcst_periods.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::met_office::text_forecast::SitesResponse;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_national_parks() {
+        let response: SitesResponse<NationalPark> =
+            serde_json::from_str(include_str!("../../fixtures/national_park_sitelist.json"))
+                .unwrap();
+        let parks = response.locations.location;
+        assert_eq!(parks.len(), 3);
+        assert_eq!(parks[0].id, "500");
+        assert_eq!(parks[0].name, "Brecon Beacons");
+    }
+
+    #[test]
+    fn test_national_park_forecast() {
+        let response: NationalParkForecastResponse =
+            serde_json::from_str(include_str!("../../fixtures/national_park_forecast.json"))
+                .unwrap();
+        let forecast: NationalParkForecast = response.try_into().unwrap();
+        assert_eq!(forecast.park.name, "Lake District");
+        assert_eq!(
+            forecast.issued_at,
+            parse_issue_time("2020-10-17T16:00:00").unwrap()
+        );
+        assert_eq!(forecast.periods.len(), 2);
+        assert_eq!(
+            forecast.periods[0].headline.as_deref(),
+            Some("Mostly dry with some sunshine.")
+        );
+        assert_eq!(forecast.periods[1].paragraphs.len(), 1);
+    }
+}
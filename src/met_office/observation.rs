@@ -0,0 +1,146 @@
+use crate::met_office::forecast_response::ObservationRep;
+use crate::met_office::{
+    direction::{Direction, DirectionConversionError},
+    pressure_tendency::{PressureTendency, PressureTendencyConversionError},
+    weather_type::{WeatherType, WeatherTypeCodeConversionError},
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use juniper::GraphQLObject;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::ops::Add;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Clone, GraphQLObject, Serialize)]
+pub struct Observation {
+    location_id: i32,
+    timestamp: NaiveDateTime,
+    wind_gust: Option<i32>,
+    temperature: Option<f64>,
+    /// Visibility in metres
+    visibility: Option<i32>,
+    wind_direction: Option<Direction>,
+    wind_speed: Option<i32>,
+    weather_type: Option<WeatherType>,
+    /// Pressure in hectopascals
+    pressure: Option<i32>,
+    pressure_tendency: Option<PressureTendency>,
+    dew_point: Option<f64>,
+    screen_relative_humidity: Option<f64>,
+}
+
+#[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
+pub enum ObservationConversionError {
+    #[error("Direction Conversion Error: {0:?}")]
+    DirectionConversionError(#[from] DirectionConversionError),
+    #[error("Pressure Tendency Conversion Error: {0:?}")]
+    PressureTendencyConversionError(#[from] PressureTendencyConversionError),
+    #[error("Weather Type Code Conversion Error: {0:?}")]
+    WeatherTypeCodeConversionError(#[from] WeatherTypeCodeConversionError),
+    #[error("Could not parse the date: {0}")]
+    DateTimeParseError(String),
+    #[error("Could not parse number: {0:?}")]
+    NumberParseError(#[from] std::num::ParseIntError),
+    #[error("Could not parse decimal: {0:?}")]
+    DecimalParseError(#[from] std::num::ParseFloatError),
+}
+
+fn parse_optional<T, E>(value: &Option<String>) -> Result<Option<T>, ObservationConversionError>
+where
+    T: FromStr<Err = E>,
+    ObservationConversionError: From<E>,
+{
+    Ok(value.as_deref().map(T::from_str).transpose()?)
+}
+
+impl TryFrom<(i32, &str, &ObservationRep)> for Observation {
+    type Error = ObservationConversionError;
+
+    fn try_from(
+        (location_id, date_time_str, weather): (i32, &str, &ObservationRep),
+    ) -> Result<Self, Self::Error> {
+        let date = NaiveDate::parse_from_str(date_time_str, "%Y-%m-%dZ").map_err(|_| {
+            ObservationConversionError::DateTimeParseError(date_time_str.to_string())
+        })?;
+        let minutes = Duration::minutes(weather.dollar.parse()?);
+        Ok(Self {
+            location_id,
+            timestamp: date.and_hms(0, 0, 0).add(minutes),
+            wind_gust: parse_optional(&weather.g)?,
+            temperature: parse_optional(&weather.t)?,
+            visibility: parse_optional(&weather.v)?,
+            wind_direction: parse_optional(&weather.d)?,
+            wind_speed: parse_optional(&weather.s)?,
+            weather_type: parse_optional(&weather.w)?,
+            pressure: parse_optional(&weather.p)?,
+            pressure_tendency: parse_optional(&weather.pt)?,
+            dew_point: parse_optional(&weather.dp)?,
+            screen_relative_humidity: parse_optional(&weather.h)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::met_office::forecast_response::ForecastResponse;
+    use crate::met_office::Observations;
+    use std::convert::TryInto;
+
+    fn fixture() -> Observations {
+        let response: ForecastResponse<ObservationRep> =
+            serde_json::from_str(include_str!("../../fixtures/observations.json")).unwrap();
+        response.try_into().unwrap()
+    }
+
+    #[test]
+    fn test_observations() {
+        let observations = fixture();
+        assert_eq!(observations.len(), 3);
+
+        let first = &observations[0];
+        assert_eq!(first.location_id, 3840);
+        assert_eq!(
+            first.timestamp,
+            NaiveDateTime::parse_from_str("2020-10-17 22:00:00", "%Y-%m-%d %H:%M:%S").unwrap()
+        );
+        assert_eq!(first.temperature, Some(9.4));
+        assert_eq!(first.visibility, Some(30000));
+        assert_eq!(first.pressure, Some(1021));
+        assert_eq!(first.pressure_tendency, Some(PressureTendency::Rising));
+        assert_eq!(first.dew_point, Some(6.6));
+        assert_eq!(first.wind_gust, None);
+
+        let last = &observations[2];
+        assert_eq!(
+            last.timestamp,
+            NaiveDateTime::parse_from_str("2020-10-18 00:00:00", "%Y-%m-%d %H:%M:%S").unwrap()
+        );
+        assert_eq!(last.wind_gust, Some(25));
+        assert_eq!(last.weather_type, Some(WeatherType::ClearNight));
+    }
+
+    #[test]
+    fn test_invalid_pressure_tendency() {
+        let rep: ObservationRep = serde_json::from_str(r#"{"Pt": "X", "$": "0"}"#).unwrap();
+        assert!(matches!(
+            Observation::try_from((1, "2020-10-17Z", &rep)),
+            Err(ObservationConversionError::PressureTendencyConversionError(
+                _
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_date() {
+        let rep: ObservationRep = serde_json::from_str(r#"{"$": "0"}"#).unwrap();
+        for date in &["2020", "2020-10-1éZ", "not a date"] {
+            assert!(matches!(
+                Observation::try_from((1, *date, &rep)),
+                Err(ObservationConversionError::DateTimeParseError(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::met_office::forecast_response::{ForecastLocation, ForecastResponse, ObservationRep};
+use crate::met_office::{Observation, ObservationConversionError};
+use std::convert::{TryFrom, TryInto};
+use std::ops::{Deref, DerefMut};
+
+#[derive(Clone)]
+pub struct Observations(pub Vec<Observation>);
+
+impl Deref for Observations {
+    type Target = Vec<Observation>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Observations {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl TryFrom<&ForecastLocation<ObservationRep>> for Observations {
+    type Error = ObservationConversionError;
+
+    fn try_from(location: &ForecastLocation<ObservationRep>) -> Result<Self, Self::Error> {
+        let location_id = location.i.parse()?;
+        let observations: std::result::Result<Vec<_>, ObservationConversionError> = location
+            .period
+            .iter()
+            .flat_map(|period| {
+                let date_time = period.value.as_str();
+                period
+                    .rep
+                    .iter()
+                    .map(move |rep| Observation::try_from((location_id, date_time, rep)))
+            })
+            .collect();
+        Ok(Self(observations?))
+    }
+}
+
+impl TryFrom<ForecastResponse<ObservationRep>> for Observations {
+    type Error = ObservationConversionError;
+
+    fn try_from(value: ForecastResponse<ObservationRep>) -> Result<Self, Self::Error> {
+        let mut observations = Vec::new();
+        for location in value.site_rep.dv.location.iter() {
+            let location_observations: Observations = location.try_into()?;
+            observations.extend(location_observations.0);
+        }
+        Ok(Self(observations))
+    }
+}
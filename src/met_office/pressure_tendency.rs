@@ -0,0 +1,61 @@
+use juniper::GraphQLEnum;
+use serde::Serialize;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Clone, Debug, Eq, PartialEq, GraphQLEnum, Serialize)]
+pub enum PressureTendency {
+    Falling,
+    Rising,
+    Steady,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PressureTendencyConversionError {
+    #[error("invalid pressure tendency, expected F, R or S, found {0}")]
+    InvalidCode(String),
+}
+
+impl FromStr for PressureTendency {
+    type Err = PressureTendencyConversionError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        match code {
+            "F" => Ok(PressureTendency::Falling),
+            "R" => Ok(PressureTendency::Rising),
+            "S" => Ok(PressureTendency::Steady),
+            _ => Err(PressureTendencyConversionError::InvalidCode(
+                code.to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_valid_pressure_tendency_codes() {
+        assert_eq!(
+            PressureTendency::from_str("F").unwrap(),
+            PressureTendency::Falling
+        );
+        assert_eq!(
+            PressureTendency::from_str("R").unwrap(),
+            PressureTendency::Rising
+        );
+        assert_eq!(
+            PressureTendency::from_str("S").unwrap(),
+            PressureTendency::Steady
+        );
+    }
+
+    #[test]
+    fn test_unknown_code() {
+        assert_eq!(
+            PressureTendency::from_str("An invalid code").unwrap_err(),
+            PressureTendencyConversionError::InvalidCode("An invalid code".to_string())
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Keeps within DataPoint's per minute and per day request limits for each API key
+use juniper::GraphQLObject;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const MINUTE: Duration = Duration::from_secs(60);
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+/// How much of a key is shown in the quota, enough to tell keys apart
+const KEY_PREFIX_LENGTH: usize = 4;
+
+/// The DataPoint fair use limits
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub per_minute: u32,
+    pub per_day: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            per_minute: 100,
+            per_day: 5000,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiKey {
+    pub key: String,
+    pub rate_limit: RateLimit,
+}
+
+impl ApiKey {
+    pub fn new(key: &str) -> ApiKey {
+        ApiKey {
+            key: key.to_string(),
+            rate_limit: RateLimit::default(),
+        }
+    }
+}
+
+/// Refills continuously, so a budget used up at once comes back over the period
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, period: Duration, now: Instant) -> TokenBucket {
+        let capacity = f64::from(capacity);
+        TokenBucket {
+            capacity,
+            tokens: capacity,
+            refill_per_second: capacity / period.as_secs_f64(),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.updated = now;
+    }
+
+    fn remaining(&self) -> i32 {
+        self.tokens.floor() as i32
+    }
+}
+
+struct KeyBudget {
+    minute: TokenBucket,
+    day: TokenBucket,
+}
+
+impl KeyBudget {
+    fn new(rate_limit: RateLimit, now: Instant) -> KeyBudget {
+        KeyBudget {
+            minute: TokenBucket::new(rate_limit.per_minute, MINUTE, now),
+            day: TokenBucket::new(rate_limit.per_day, DAY, now),
+        }
+    }
+
+    /// Only takes from either bucket when both have a request left
+    fn try_take(&mut self, now: Instant) -> bool {
+        self.minute.refill(now);
+        self.day.refill(now);
+        if self.minute.tokens < 1.0 || self.day.tokens < 1.0 {
+            return false;
+        }
+        self.minute.tokens -= 1.0;
+        self.day.tokens -= 1.0;
+        true
+    }
+}
+
+/// The requests left for an API key
+#[derive(GraphQLObject)]
+pub struct Quota {
+    /// The start of the API key
+    key: String,
+    per_minute: i32,
+    per_day: i32,
+    remaining_this_minute: i32,
+    remaining_today: i32,
+}
+
+/// Only the server's keys have a budget. Keys sent by clients are theirs to spend, DataPoint
+/// limits them, so keeping a budget for each would only let clients grow the map.
+pub struct RateLimiter {
+    budgets: Mutex<HashMap<String, KeyBudget>>,
+}
+
+impl RateLimiter {
+    pub fn new(api_keys: &[ApiKey]) -> RateLimiter {
+        let now = Instant::now();
+        let budgets = api_keys
+            .iter()
+            .map(|api_key| (api_key.key.clone(), KeyBudget::new(api_key.rate_limit, now)))
+            .collect();
+        RateLimiter {
+            budgets: Mutex::new(budgets),
+        }
+    }
+
+    pub fn try_acquire(&self, api_key: &ApiKey) -> bool {
+        self.try_acquire_at(api_key, Instant::now())
+    }
+
+    fn try_acquire_at(&self, api_key: &ApiKey, now: Instant) -> bool {
+        match self.budgets.lock().unwrap().get_mut(&api_key.key) {
+            Some(budget) => budget.try_take(now),
+            None => true,
+        }
+    }
+
+    pub fn quota(&self, api_key: &ApiKey) -> Quota {
+        self.quota_at(api_key, Instant::now())
+    }
+
+    fn quota_at(&self, api_key: &ApiKey, now: Instant) -> Quota {
+        let mut budgets = self.budgets.lock().unwrap();
+        let mut unlimited = KeyBudget::new(api_key.rate_limit, now);
+        let budget = budgets.get_mut(&api_key.key).unwrap_or(&mut unlimited);
+        budget.minute.refill(now);
+        budget.day.refill(now);
+        Quota {
+            key: api_key.key.chars().take(KEY_PREFIX_LENGTH).collect(),
+            per_minute: i32::try_from(api_key.rate_limit.per_minute).unwrap_or(i32::MAX),
+            per_day: i32::try_from(api_key.rate_limit.per_day).unwrap_or(i32::MAX),
+            remaining_this_minute: budget.minute.remaining(),
+            remaining_today: budget.day.remaining(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_key(per_minute: u32, per_day: u32) -> ApiKey {
+        ApiKey {
+            key: "abcdefgh".to_string(),
+            rate_limit: RateLimit {
+                per_minute,
+                per_day,
+            },
+        }
+    }
+
+    #[test]
+    fn test_minute_budget_refills() {
+        let api_key = api_key(2, 100);
+        let limiter = RateLimiter::new(std::slice::from_ref(&api_key));
+        let now = Instant::now();
+        assert!(limiter.try_acquire_at(&api_key, now));
+        assert!(limiter.try_acquire_at(&api_key, now));
+        assert!(!limiter.try_acquire_at(&api_key, now));
+        assert!(limiter.try_acquire_at(&api_key, now + Duration::from_secs(30)));
+        assert!(!limiter.try_acquire_at(&api_key, now + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_day_budget() {
+        let api_key = api_key(10, 2);
+        let limiter = RateLimiter::new(std::slice::from_ref(&api_key));
+        let now = Instant::now();
+        assert!(limiter.try_acquire_at(&api_key, now));
+        assert!(limiter.try_acquire_at(&api_key, now + MINUTE));
+        assert!(!limiter.try_acquire_at(&api_key, now + MINUTE * 2));
+
+        let quota = limiter.quota_at(&api_key, now + MINUTE * 2);
+        assert_eq!(quota.key, "abcd");
+        assert_eq!(quota.remaining_this_minute, 10);
+        assert_eq!(quota.remaining_today, 0);
+    }
+
+    #[test]
+    fn test_client_keys_have_no_budget() {
+        let limiter = RateLimiter::new(&[api_key(1, 1)]);
+        let client_key = ApiKey {
+            key: "client".to_string(),
+            rate_limit: RateLimit {
+                per_minute: 1,
+                per_day: 1,
+            },
+        };
+        let now = Instant::now();
+        assert!(limiter.try_acquire_at(&client_key, now));
+        assert!(limiter.try_acquire_at(&client_key, now));
+        assert_eq!(limiter.quota_at(&client_key, now).remaining_today, 1);
+        assert_eq!(limiter.budgets.lock().unwrap().len(), 1);
+    }
+}
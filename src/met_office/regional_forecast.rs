@@ -0,0 +1,100 @@
+use crate::met_office::text_forecast::{parse_issue_time, FcstPeriods, TextForecastPeriod};
+use crate::met_office::ForecastConversionError;
+use chrono::NaiveDateTime;
+use juniper::GraphQLObject;
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+/// A region with a written forecast, the name matches `Location.region`
+#[derive(Debug, Deserialize, GraphQLObject)]
+pub struct Region {
+    #[serde(rename = "@id")]
+    pub id: String,
+    #[serde(rename = "@name")]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RegionalForecastResponse {
+    pub regional_fcst: RegionalFcst,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegionalFcst {
+    #[serde(rename = "createdOn")]
+    pub created_on: String,
+    #[serde(rename = "issuedAt")]
+    pub issued_at: String,
+    #[serde(rename = "regionId")]
+    pub region_id: String,
+    #[serde(rename = "FcstPeriods")]
+    pub fcst_periods: FcstPeriods,
+}
+
+#[derive(GraphQLObject)]
+pub struct RegionalForecast {
+    region: String,
+    created_on: NaiveDateTime,
+    issued_at: NaiveDateTime,
+    periods: Vec<TextForecastPeriod>,
+}
+
+impl TryFrom<RegionalForecastResponse> for RegionalForecast {
+    type Error = ForecastConversionError;
+
+    fn try_from(value: RegionalForecastResponse) -> Result<Self, Self::Error> {
+        let forecast = value.regional_fcst;
+        Ok(Self {
+            created_on: parse_issue_time(&forecast.created_on)?,
+            issued_at: parse_issue_time(&forecast.issued_at)?,
+            region: forecast.region_id,
+            periods: forecast.fcst_periods.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::met_office::text_forecast::SitesResponse;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_regions() {
+        let response: SitesResponse<Region> = serde_json::from_str(include_str!(
+            "../../fixtures/regional_forecast_sitelist.json"
+        ))
+        .unwrap();
+        let regions = response.locations.location;
+        assert_eq!(regions.len(), 17);
+        assert_eq!(regions[11].id, "511");
+        assert_eq!(regions[11].name, "se");
+    }
+
+    #[test]
+    fn test_regional_forecast() {
+        let response: RegionalForecastResponse =
+            serde_json::from_str(include_str!("../../fixtures/regional_forecast.json")).unwrap();
+        let forecast: RegionalForecast = response.try_into().unwrap();
+        assert_eq!(forecast.region, "se");
+        assert_eq!(
+            forecast.issued_at,
+            parse_issue_time("2020-10-17T16:00:00").unwrap()
+        );
+        assert_eq!(forecast.periods.len(), 3);
+
+        let first = &forecast.periods[0];
+        assert_eq!(first.id, "day1to2");
+        assert_eq!(
+            first.headline.as_deref(),
+            Some("Dry with sunny spells, chilly overnight.")
+        );
+        assert_eq!(first.paragraphs.len(), 2);
+        assert_eq!(first.paragraphs[0].title, "This Evening and Tonight:");
+
+        let outlook = &forecast.periods[1];
+        assert_eq!(outlook.headline, None);
+        assert_eq!(outlook.paragraphs.len(), 1);
+    }
+}
@@ -0,0 +1,478 @@
+//! Caches DataPoint responses so repeated queries don't use up the quota
+use crate::met_office::capabilities::parse_time_step;
+#[cfg(feature = "disk-cache")]
+use crate::met_office::disk_cache::DiskCache;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use juniper::GraphQLObject;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+#[cfg(feature = "disk-cache")]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Site lists hardly ever change
+pub const SITE_LIST_EXPIRY: Expiry = Expiry::After(Duration::from_secs(24 * 60 * 60));
+const DEFAULT_TTL: Duration = Duration::from_secs(15 * 60);
+/// Used when the next update is already due, so a late upstream isn't asked on every query
+const MIN_TTL: Duration = Duration::from_secs(5 * 60);
+/// DataPoint publishes new forecasts and observations every hour
+const PUBLISH_CADENCE: i64 = 60 * 60;
+/// How long after expiring a response may still be served while DataPoint is unavailable
+const DEFAULT_MAX_STALE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long a response is kept for
+#[derive(Clone, Copy, Debug)]
+pub enum Expiry {
+    Default,
+    After(Duration),
+    /// Until the next update after the response's `dataDate` is published
+    DataDate,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DataDateResponse {
+    site_rep: DataDateSiteRep,
+}
+
+#[derive(Deserialize)]
+struct DataDateSiteRep {
+    #[serde(rename = "DV")]
+    dv: DataDateDv,
+}
+
+#[derive(Deserialize)]
+struct DataDateDv {
+    #[serde(rename = "dataDate")]
+    data_date: String,
+}
+
+impl Expiry {
+    pub fn ttl(self, body: &str, now: NaiveDateTime) -> Duration {
+        match self {
+            Expiry::Default => DEFAULT_TTL,
+            Expiry::After(ttl) => ttl,
+            Expiry::DataDate => serde_json::from_str::<DataDateResponse>(body)
+                .ok()
+                .and_then(|response| parse_time_step(&response.site_rep.dv.data_date).ok())
+                .and_then(|data_date| {
+                    (data_date + chrono::Duration::seconds(PUBLISH_CADENCE) - now)
+                        .to_std()
+                        .ok()
+                })
+                .filter(|ttl| *ttl > MIN_TTL)
+                .unwrap_or(MIN_TTL),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ResponseCacheConfig {
+    pub max_entries: usize,
+    pub max_bytes: usize,
+    pub max_stale: Duration,
+    /// Where responses are kept between restarts
+    #[cfg(feature = "disk-cache")]
+    pub disk_dir: Option<PathBuf>,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        ResponseCacheConfig {
+            max_entries: 1000,
+            max_bytes: 64 * 1024 * 1024,
+            max_stale: DEFAULT_MAX_STALE,
+            #[cfg(feature = "disk-cache")]
+            disk_dir: None,
+        }
+    }
+}
+
+#[derive(GraphQLObject)]
+pub struct CacheStats {
+    hits: f64,
+    misses: f64,
+    hit_rate: f64,
+    /// Expired responses served because DataPoint was unavailable
+    stale_served: f64,
+    entries: i32,
+    bytes: f64,
+    /// When the oldest cached response was fetched, it may be from before a restart
+    oldest_fetched_at: Option<NaiveDateTime>,
+}
+
+/// Whether a response is up to date, or kept from before DataPoint became unavailable
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Freshness {
+    pub stale: bool,
+    pub fetched_at: NaiveDateTime,
+}
+
+#[derive(Clone)]
+pub struct CachedBody {
+    pub body: Arc<str>,
+    pub freshness: Freshness,
+}
+
+struct CachedResponse {
+    body: Arc<str>,
+    fetched_at: SystemTime,
+    expires: Instant,
+}
+
+impl CachedResponse {
+    fn cached_body(&self, stale: bool) -> CachedBody {
+        CachedBody {
+            body: self.body.clone(),
+            freshness: Freshness {
+                stale,
+                fetched_at: DateTime::<Utc>::from(self.fetched_at).naive_utc(),
+            },
+        }
+    }
+
+    /// Whether it expired less than `max_stale` before `now`, a `max_stale` too long to add to the
+    /// expiry never runs out
+    fn is_servable(&self, max_stale: Duration, now: Instant) -> bool {
+        match self.expires.checked_add(max_stale) {
+            Some(servable_until) => servable_until > now,
+            None => true,
+        }
+    }
+}
+
+#[derive(Default)]
+struct CachedResponses {
+    responses: HashMap<String, CachedResponse>,
+    order: VecDeque<String>,
+    bytes: usize,
+}
+
+impl CachedResponses {
+    fn remove(&mut self, key: &str) {
+        if let Some(response) = self.responses.remove(key) {
+            self.bytes -= response.body.len();
+            self.order.retain(|cached| cached != key);
+        }
+    }
+}
+
+/// Response bodies by request path, the oldest are dropped once either size limit is reached
+///
+/// Expired responses are kept until then, or until they are pruned `max_stale` after expiring, to
+/// be served if DataPoint is unavailable.
+pub struct ResponseCache {
+    config: ResponseCacheConfig,
+    responses: Mutex<CachedResponses>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stale_served: AtomicU64,
+    #[cfg(feature = "disk-cache")]
+    disk: Option<DiskCache>,
+}
+
+impl ResponseCache {
+    pub fn new(config: ResponseCacheConfig) -> ResponseCache {
+        let cache = ResponseCache {
+            #[cfg(feature = "disk-cache")]
+            disk: config.disk_dir.clone().map(DiskCache::new),
+            config,
+            responses: Mutex::new(CachedResponses::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            stale_served: AtomicU64::new(0),
+        };
+        #[cfg(feature = "disk-cache")]
+        cache.load_from_disk();
+        cache
+    }
+
+    #[cfg(feature = "disk-cache")]
+    fn load_from_disk(&self) {
+        let now = SystemTime::now();
+        let stored = match &self.disk {
+            Some(disk) => disk.load(now, self.config.max_stale),
+            None => return,
+        };
+        let instant = Instant::now();
+        for response in stored {
+            let expires = match response.expires_at().duration_since(now) {
+                Ok(ttl) => instant + ttl,
+                Err(expired) => instant.checked_sub(expired.duration()).unwrap_or(instant),
+            };
+            let fetched_at = response.fetched_at();
+            self.insert_entry(response.key, response.body.into(), fetched_at, expires);
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<CachedBody> {
+        let body = self.get_fresh(key);
+        let counter = if body.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        body
+    }
+
+    /// Like `get`, without counting towards the hit rate
+    pub fn get_fresh(&self, key: &str) -> Option<CachedBody> {
+        let cached = self.responses.lock().unwrap();
+        cached
+            .responses
+            .get(key)
+            .filter(|response| response.expires > Instant::now())
+            .map(|response| response.cached_body(false))
+    }
+
+    /// An expired response, as long as it expired less than `max_stale` ago
+    pub fn get_stale(&self, key: &str) -> Option<CachedBody> {
+        let cached = self.responses.lock().unwrap();
+        let body = cached
+            .responses
+            .get(key)
+            .filter(|response| response.is_servable(self.config.max_stale, Instant::now()))
+            .map(|response| response.cached_body(true));
+        if body.is_some() {
+            self.stale_served.fetch_add(1, Ordering::Relaxed);
+        }
+        body
+    }
+
+    pub fn insert(&self, key: String, body: String, ttl: Duration) -> CachedBody {
+        let fetched_at = SystemTime::now();
+        #[cfg(feature = "disk-cache")]
+        {
+            if let Some(disk) = &self.disk {
+                disk.store(&key, &body, fetched_at, fetched_at + ttl);
+            }
+        }
+        self.insert_entry(key, body.into(), fetched_at, Instant::now() + ttl)
+    }
+
+    fn insert_entry(
+        &self,
+        key: String,
+        body: Arc<str>,
+        fetched_at: SystemTime,
+        expires: Instant,
+    ) -> CachedBody {
+        let response = CachedResponse {
+            body,
+            fetched_at,
+            expires,
+        };
+        let cached_body = response.cached_body(false);
+        let mut removed = Vec::new();
+        {
+            let mut cached = self.responses.lock().unwrap();
+            cached.remove(&key);
+            if response.body.len() > self.config.max_bytes {
+                removed.push(key);
+            } else {
+                cached.bytes += response.body.len();
+                cached.order.push_back(key.clone());
+                cached.responses.insert(key, response);
+            }
+            while cached.order.len() > self.config.max_entries
+                || cached.bytes > self.config.max_bytes
+            {
+                if let Some(oldest) = cached.order.pop_front() {
+                    cached.remove(&oldest);
+                    removed.push(oldest);
+                }
+            }
+        }
+        self.removed(removed);
+        cached_body
+    }
+
+    /// Drops the responses that expired too long ago to be served stale
+    pub fn prune(&self) {
+        let now = Instant::now();
+        let removed: Vec<_> = {
+            let mut cached = self.responses.lock().unwrap();
+            let expired: Vec<_> = cached
+                .responses
+                .iter()
+                .filter(|(_, response)| !response.is_servable(self.config.max_stale, now))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in &expired {
+                cached.remove(key);
+            }
+            expired
+        };
+        self.removed(removed);
+    }
+
+    /// Deletes the disk copies of responses that are no longer cached
+    fn removed(&self, keys: Vec<String>) {
+        #[cfg(feature = "disk-cache")]
+        {
+            if let Some(disk) = &self.disk {
+                keys.iter().for_each(|key| disk.remove(key));
+            }
+        }
+        #[cfg(not(feature = "disk-cache"))]
+        let _ = keys;
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let cached = self.responses.lock().unwrap();
+        let hits = self.hits.load(Ordering::Relaxed) as f64;
+        let misses = self.misses.load(Ordering::Relaxed) as f64;
+        CacheStats {
+            hits,
+            misses,
+            hit_rate: if hits + misses > 0.0 {
+                hits / (hits + misses)
+            } else {
+                0.0
+            },
+            stale_served: self.stale_served.load(Ordering::Relaxed) as f64,
+            entries: i32::try_from(cached.responses.len()).unwrap_or(i32::MAX),
+            bytes: cached.bytes as f64,
+            oldest_fetched_at: cached
+                .responses
+                .values()
+                .map(|response| response.fetched_at)
+                .min()
+                .map(|fetched_at| DateTime::<Utc>::from(fetched_at).naive_utc()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_entries: usize, max_bytes: usize) -> ResponseCache {
+        ResponseCache::new(ResponseCacheConfig {
+            max_entries,
+            max_bytes,
+            ..ResponseCacheConfig::default()
+        })
+    }
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    #[test]
+    fn test_hits_and_misses() {
+        let cache = cache(10, 1000);
+        assert!(cache.get("a").is_none());
+        cache.insert("a".to_string(), "{}".to_string(), HOUR);
+        assert_eq!(&*cache.get("a").unwrap().body, "{}");
+        cache.insert("b".to_string(), "{}".to_string(), Duration::from_secs(0));
+        assert!(cache.get("b").is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1.0);
+        assert_eq!(stats.misses, 2.0);
+        assert_eq!(stats.entries, 2);
+        assert!(stats.oldest_fetched_at.is_some());
+    }
+
+    #[test]
+    fn test_stale_responses() {
+        let cache = cache(10, 1000);
+        cache.insert("a".to_string(), "{}".to_string(), Duration::from_secs(0));
+        assert!(cache.get("a").is_none());
+        let stale = cache.get_stale("a").unwrap();
+        assert_eq!(&*stale.body, "{}");
+        assert!(stale.freshness.stale);
+        assert!(cache.get_stale("b").is_none());
+        assert_eq!(cache.stats().stale_served, 1.0);
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let cache = cache(2, 10);
+        cache.insert("a".to_string(), "aaaa".to_string(), HOUR);
+        cache.insert("b".to_string(), "bbbb".to_string(), HOUR);
+        cache.insert("c".to_string(), "cccc".to_string(), HOUR);
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.stats().bytes, 8.0);
+
+        cache.insert("d".to_string(), "d".repeat(11), HOUR);
+        assert!(cache.get("d").is_none());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn test_data_date_expiry() {
+        let body = include_str!("../../fixtures/daily_forecast.json");
+        let now = parse_time_step("2020-10-17T15:20:00Z").unwrap();
+        assert_eq!(
+            Expiry::DataDate.ttl(body, now),
+            Duration::from_secs(40 * 60)
+        );
+
+        let late = parse_time_step("2020-10-17T16:30:00Z").unwrap();
+        assert_eq!(Expiry::DataDate.ttl(body, late), MIN_TTL);
+        assert_eq!(Expiry::DataDate.ttl("<html></html>", now), MIN_TTL);
+    }
+
+    #[test]
+    fn test_prune() {
+        let cache = ResponseCache::new(ResponseCacheConfig {
+            max_stale: Duration::from_secs(0),
+            ..ResponseCacheConfig::default()
+        });
+        cache.insert("a".to_string(), "{}".to_string(), Duration::from_secs(0));
+        cache.insert("b".to_string(), "{}".to_string(), HOUR);
+        cache.prune();
+        assert_eq!(cache.stats().entries, 1);
+        assert!(cache.get("b").is_some());
+    }
+
+    #[test]
+    fn test_max_stale_too_long_to_add() {
+        let cache = ResponseCache::new(ResponseCacheConfig {
+            max_stale: Duration::from_secs(u64::MAX),
+            ..ResponseCacheConfig::default()
+        });
+        cache.insert("a".to_string(), "{}".to_string(), Duration::from_secs(0));
+        cache.prune();
+        assert!(cache.get_stale("a").is_some());
+    }
+
+    #[cfg(feature = "disk-cache")]
+    #[test]
+    fn test_evicted_responses_leave_the_disk() {
+        let dir = std::env::temp_dir().join(format!("weather-api-evict-{}", std::process::id()));
+        let config = ResponseCacheConfig {
+            max_entries: 1,
+            disk_dir: Some(dir.clone()),
+            ..ResponseCacheConfig::default()
+        };
+        let cache = ResponseCache::new(config.clone());
+        cache.insert("a".to_string(), "{}".to_string(), HOUR);
+        cache.insert("b".to_string(), "{}".to_string(), HOUR);
+        drop(cache);
+
+        let restarted = ResponseCache::new(config);
+        assert!(restarted.get("a").is_none());
+        assert!(restarted.get("b").is_some());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "disk-cache")]
+    #[test]
+    fn test_warm_start_from_disk() {
+        let dir = std::env::temp_dir().join(format!("weather-api-warm-{}", std::process::id()));
+        let config = ResponseCacheConfig {
+            disk_dir: Some(dir.clone()),
+            ..ResponseCacheConfig::default()
+        };
+        ResponseCache::new(config.clone()).insert("a".to_string(), "{}".to_string(), HOUR);
+
+        let restarted = ResponseCache::new(config);
+        assert_eq!(&*restarted.get("a").unwrap().body, "{}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
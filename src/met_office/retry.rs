@@ -0,0 +1,53 @@
+//! Retries DataPoint requests that failed for reasons that may pass, such as a dropped connection
+use rand::Rng;
+use std::time::Duration;
+
+#[derive(Clone, Debug)]
+pub struct RetryConfig {
+    /// Retries after the first attempt, zero turns retrying off
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// The longest wait before a retry, doubling with each one up to `max_backoff`
+    fn backoff_ceiling(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .checked_mul(2u32.saturating_pow(retry))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// A random wait up to the ceiling, so clients that failed together don't retry together
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.backoff_ceiling(retry)
+            .mul_f64(rand::thread_rng().gen_range(0.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_maximum() {
+        let config = RetryConfig::default();
+        assert_eq!(config.backoff_ceiling(0), Duration::from_millis(200));
+        assert_eq!(config.backoff_ceiling(1), Duration::from_millis(400));
+        assert_eq!(config.backoff_ceiling(3), Duration::from_millis(1600));
+        assert_eq!(config.backoff_ceiling(4), Duration::from_secs(2));
+        assert_eq!(config.backoff_ceiling(40), Duration::from_secs(2));
+        assert!(config.backoff(1) < Duration::from_millis(400));
+    }
+}
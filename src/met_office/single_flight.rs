@@ -0,0 +1,109 @@
+//! Lets concurrent identical DataPoint requests share one upstream fetch
+//!
+//! Many clients ask for the same forecast as soon as the cached one expires, only the first
+//! request goes upstream and the rest wait for its response body.
+use crate::met_office::MetApiError;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+type SharedResult<T> = Result<T, Arc<MetApiError>>;
+type Flights<T> = Arc<Mutex<HashMap<String, Shared<BoxFuture<'static, SharedResult<T>>>>>>;
+
+pub struct SingleFlight<T> {
+    in_flight: Flights<T>,
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        SingleFlight {
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> SingleFlight<T> {
+    /// Runs `fetch` unless a fetch for the same key is already running, then waits for that one
+    pub async fn run<F>(&self, key: String, fetch: F) -> Result<T, MetApiError>
+    where
+        F: Future<Output = Result<T, MetApiError>> + Send + 'static,
+    {
+        let flight = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| {
+                let in_flight = self.in_flight.clone();
+                async move {
+                    let result = fetch.await.map_err(Arc::new);
+                    in_flight.lock().unwrap().remove(&key);
+                    result
+                }
+                .boxed()
+                .shared()
+            })
+            .clone();
+        flight
+            .await
+            .map_err(|error| Arc::try_unwrap(error).unwrap_or_else(MetApiError::Shared))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::oneshot;
+    use futures::executor::block_on;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_concurrent_fetches_are_shared() {
+        let flight = SingleFlight::<i32>::default();
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let (sender, receiver) = oneshot::channel::<()>();
+
+        let first = flight.run("forecast".to_string(), {
+            let fetches = fetches.clone();
+            async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                receiver.await.unwrap();
+                Ok(1)
+            }
+        });
+        let second = flight.run("forecast".to_string(), {
+            let fetches = fetches.clone();
+            async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                Ok(2)
+            }
+        });
+        let (first, second, _) =
+            block_on(async { futures::join!(first, second, async { sender.send(()).unwrap() }) });
+        assert_eq!(first.unwrap(), 1);
+        assert_eq!(second.unwrap(), 1);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        let later = block_on(flight.run("forecast".to_string(), async { Ok(3) }));
+        assert_eq!(later.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_errors_are_shared() {
+        let flight = SingleFlight::<i32>::default();
+        let (sender, receiver) = oneshot::channel::<()>();
+        let first = flight.run("forecast".to_string(), async move {
+            receiver.await.unwrap();
+            Err(MetApiError::NotFound(String::new()))
+        });
+        let second = flight.run("forecast".to_string(), async { Ok(2) });
+        let (first, second, _) =
+            block_on(async { futures::join!(first, second, async { sender.send(()).unwrap() }) });
+        assert!(first.is_err());
+        assert!(matches!(
+            second,
+            Err(MetApiError::Shared(error)) if matches!(*error, MetApiError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::met_office::capabilities::parse_time_step;
+use crate::met_office::forecast_response::one_or_many;
+use crate::met_office::ForecastConversionError;
+use chrono::NaiveDateTime;
+use juniper::GraphQLObject;
+use serde::Deserialize;
+use std::convert::TryFrom;
+
+/// Where the proxied charts are served from, see `image_proxy`
+pub const CHART_PROXY_PATH: &str = "/charts/surfacepressure";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SurfacePressureResponse {
+    #[serde(rename = "BWSurfacePressureChartList")]
+    pub chart_list: ChartList,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChartList {
+    #[serde(rename = "BWSurfacePressureChart", deserialize_with = "one_or_many")]
+    pub chart: Vec<Chart>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Chart {
+    pub data_date: String,
+    pub valid_from: String,
+    pub valid_to: String,
+    pub forecast_period: String,
+}
+
+#[derive(GraphQLObject)]
+pub struct SurfacePressureChart {
+    data_date: NaiveDateTime,
+    valid_from: NaiveDateTime,
+    valid_to: NaiveDateTime,
+    /// Hours ahead of the data date
+    forecast_period: i32,
+    /// Path of the chart on this server, the API key is added by the server
+    url: String,
+}
+
+impl TryFrom<Chart> for SurfacePressureChart {
+    type Error = ForecastConversionError;
+
+    fn try_from(chart: Chart) -> Result<Self, Self::Error> {
+        let forecast_period = chart.forecast_period.parse()?;
+        Ok(Self {
+            data_date: parse_time_step(&chart.data_date)?,
+            valid_from: parse_time_step(&chart.valid_from)?,
+            valid_to: parse_time_step(&chart.valid_to)?,
+            forecast_period,
+            url: format!("{}/{}", CHART_PROXY_PATH, forecast_period),
+        })
+    }
+}
+
+impl TryFrom<SurfacePressureResponse> for Vec<SurfacePressureChart> {
+    type Error = ForecastConversionError;
+
+    fn try_from(value: SurfacePressureResponse) -> Result<Self, Self::Error> {
+        value
+            .chart_list
+            .chart
+            .into_iter()
+            .map(SurfacePressureChart::try_from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_surface_pressure_charts() {
+        let response: SurfacePressureResponse = serde_json::from_str(include_str!(
+            "../../fixtures/surface_pressure_capabilities.json"
+        ))
+        .unwrap();
+        let charts: Vec<SurfacePressureChart> = response.try_into().unwrap();
+        assert_eq!(charts.len(), 2);
+        assert_eq!(charts[1].forecast_period, 12);
+        assert_eq!(
+            charts[1].valid_from,
+            parse_time_step("2020-10-18T00:00:00Z").unwrap()
+        );
+        assert_eq!(charts[1].url, "/charts/surfacepressure/12");
+    }
+}
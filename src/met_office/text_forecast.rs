@@ -0,0 +1,79 @@
+use crate::met_office::forecast_response::one_or_many;
+use crate::met_office::ForecastConversionError;
+use chrono::NaiveDateTime;
+use juniper::GraphQLObject;
+use serde::Deserialize;
+
+const HEADLINE_TITLE: &str = "Headline";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SitesResponse<T> {
+    pub locations: Sites<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Sites<T> {
+    pub location: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FcstPeriods {
+    #[serde(rename = "Period", deserialize_with = "one_or_many")]
+    pub period: Vec<FcstPeriod>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FcstPeriod {
+    pub id: String,
+    #[serde(rename = "Paragraph", deserialize_with = "one_or_many")]
+    pub paragraph: Vec<Paragraph>,
+}
+
+#[derive(Debug, Clone, Deserialize, GraphQLObject)]
+pub struct Paragraph {
+    pub title: String,
+    #[serde(rename = "$")]
+    pub content: String,
+}
+
+#[derive(GraphQLObject)]
+pub struct TextForecastPeriod {
+    /// The span of days covered, for example "day1to2"
+    pub id: String,
+    pub headline: Option<String>,
+    pub paragraphs: Vec<Paragraph>,
+}
+
+pub fn parse_issue_time(time: &str) -> Result<NaiveDateTime, ForecastConversionError> {
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S")
+        .map_err(|_| ForecastConversionError::DateTimeParseError(time.to_string()))
+}
+
+impl From<FcstPeriod> for TextForecastPeriod {
+    fn from(period: FcstPeriod) -> Self {
+        let (headlines, paragraphs): (Vec<_>, Vec<_>) = period
+            .paragraph
+            .into_iter()
+            .partition(|paragraph| paragraph.title.trim_end_matches(':') == HEADLINE_TITLE);
+        Self {
+            id: period.id,
+            headline: headlines
+                .into_iter()
+                .next()
+                .map(|headline| headline.content),
+            paragraphs,
+        }
+    }
+}
+
+impl From<FcstPeriods> for Vec<TextForecastPeriod> {
+    fn from(periods: FcstPeriods) -> Self {
+        periods
+            .period
+            .into_iter()
+            .map(TextForecastPeriod::from)
+            .collect()
+    }
+}
@@ -0,0 +1,145 @@
+//! How DataPoint requests go over the wire, swapped for fixtures in tests
+use futures::future::{BoxFuture, FutureExt};
+use isahc::prelude::*;
+
+/// Sends a GET request for a full DataPoint URL, including the API key
+pub trait Transport: Send + Sync {
+    fn get<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Result<Response<Body>, isahc::Error>>;
+}
+
+impl Transport for HttpClient {
+    fn get<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Result<Response<Body>, isahc::Error>> {
+        self.get_async(uri).boxed()
+    }
+}
+
+/// Serves canned responses by path, anything else is a 404 like DataPoint's
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeTransport {
+    responses: std::collections::HashMap<String, (u16, &'static str)>,
+    requests: std::sync::Mutex<Vec<String>>,
+    /// Keys answered with a 403, like DataPoint does for keys it doesn't know
+    rejected_keys: Vec<String>,
+    /// Responses wait for this, so tests can have requests in flight together
+    paused: Option<futures::future::Shared<futures::channel::oneshot::Receiver<()>>>,
+}
+
+#[cfg(test)]
+impl FakeTransport {
+    /// The base URL to configure `MetApi` with
+    pub const BASE_URL: &'static str = "http://datapoint.test";
+
+    /// `path` is relative to the base URL, without the `key` parameter
+    pub fn with(mut self, path: &str, body: &'static str) -> FakeTransport {
+        self.responses.insert(path.to_string(), (200, body));
+        self
+    }
+
+    pub fn with_status(mut self, path: &str, status: u16) -> FakeTransport {
+        self.responses.insert(path.to_string(), (status, ""));
+        self
+    }
+
+    pub fn rejecting_key(mut self, key: &str) -> FakeTransport {
+        self.rejected_keys.push(key.to_string());
+        self
+    }
+
+    /// Holds every response until the returned sender is used
+    pub fn paused(mut self) -> (FakeTransport, futures::channel::oneshot::Sender<()>) {
+        let (resume, paused) = futures::channel::oneshot::channel();
+        self.paused = Some(paused.shared());
+        (self, resume)
+    }
+
+    /// The paths requested so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Drops the base URL and the `key` parameter, which `MetApi` always adds last
+    fn path(uri: &str) -> String {
+        let path = uri
+            .strip_prefix(FakeTransport::BASE_URL)
+            .unwrap_or(uri)
+            .trim_start_matches('/');
+        let path = path.rsplit_once("key=").map_or(path, |(path, _)| path);
+        path.trim_end_matches(&['?', '&'][..]).to_string()
+    }
+}
+
+#[cfg(test)]
+impl super::MetApi {
+    /// A `MetApi` with one key and no retries, sending its requests to `transport`
+    pub fn fake(transport: FakeTransport) -> (super::MetApi, std::sync::Arc<FakeTransport>) {
+        super::MetApi::fake_with(transport, |_| {})
+    }
+
+    /// Like `fake`, with `configure` changing the configuration first
+    pub fn fake_with<F>(
+        transport: FakeTransport,
+        configure: F,
+    ) -> (super::MetApi, std::sync::Arc<FakeTransport>)
+    where
+        F: FnOnce(&mut super::MetApiConfig),
+    {
+        let transport = std::sync::Arc::new(transport);
+        let mut config = super::MetApiConfig::new(FakeTransport::BASE_URL);
+        config.retry.max_retries = 0;
+        configure(&mut config);
+        let api_keys = vec![super::ApiKey::new("a")];
+        let met_api = super::MetApi::with_transport(api_keys, config, transport.clone());
+        (met_api, transport)
+    }
+}
+
+#[cfg(test)]
+impl Transport for FakeTransport {
+    fn get<'a>(&'a self, uri: &'a str) -> BoxFuture<'a, Result<Response<Body>, isahc::Error>> {
+        let path = FakeTransport::path(uri);
+        let rejected = self
+            .rejected_keys
+            .iter()
+            .any(|key| uri.ends_with(&format!("key={}", key)));
+        let (status, body) = if rejected {
+            (403, "Forbidden")
+        } else {
+            self.responses
+                .get(&path)
+                .copied()
+                .unwrap_or((404, "Not Found"))
+        };
+        self.requests.lock().unwrap().push(path);
+        let response = Response::builder()
+            .status(status)
+            .header(isahc::http::header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap();
+        let paused = self.paused.clone();
+        async move {
+            if let Some(paused) = paused {
+                let _ = paused.await;
+            }
+            Ok(response)
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fake_path() {
+        assert_eq!(
+            FakeTransport::path("http://datapoint.test/val/wxfcs/all/json/sitelist?key=a"),
+            "val/wxfcs/all/json/sitelist"
+        );
+        assert_eq!(
+            FakeTransport::path("http://datapoint.test/val/wxfcs/all/json/3840?res=3hourly&key=a"),
+            "val/wxfcs/all/json/3840?res=3hourly"
+        );
+    }
+}
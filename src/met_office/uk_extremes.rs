@@ -0,0 +1,155 @@
+use crate::met_office::extreme_type::{ExtremeType, ExtremeTypeConversionError};
+use crate::met_office::forecast_response::one_or_many;
+use chrono::{NaiveDate, NaiveDateTime};
+use juniper::GraphQLObject;
+use serde::Deserialize;
+use std::convert::TryFrom;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct UkExtremesResponse {
+    pub uk_ex: UkEx,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UkEx {
+    #[serde(rename = "extremeDate")]
+    pub extreme_date: String,
+    #[serde(rename = "issuedAt")]
+    pub issued_at: String,
+    #[serde(rename = "Regions")]
+    pub regions: ExtremeRegions,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtremeRegions {
+    #[serde(rename = "Region", deserialize_with = "one_or_many")]
+    pub region: Vec<ExtremeRegion>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtremeRegion {
+    pub id: String,
+    #[serde(rename = "Extremes")]
+    pub extremes: ExtremeReports,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtremeReports {
+    #[serde(rename = "Extreme", deserialize_with = "one_or_many")]
+    pub extreme: Vec<ExtremeReport>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtremeReport {
+    #[serde(rename = "locId")]
+    pub loc_id: String,
+    #[serde(rename = "locationName")]
+    pub location_name: String,
+    #[serde(rename = "type")]
+    pub extreme_type: String,
+    pub uom: String,
+    #[serde(rename = "$")]
+    pub value: String,
+}
+
+#[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
+pub enum UkExtremesConversionError {
+    #[error("Extreme Type Conversion Error: {0:?}")]
+    ExtremeTypeConversionError(#[from] ExtremeTypeConversionError),
+    #[error("Could not parse the date: {0}")]
+    DateTimeParseError(String),
+    #[error("Could not parse decimal: {0:?}")]
+    DecimalParseError(#[from] std::num::ParseFloatError),
+}
+
+/// The previous day's extremes for each UK region
+#[derive(GraphQLObject)]
+pub struct UkExtremes {
+    pub date: NaiveDate,
+    issued_at: NaiveDateTime,
+    extremes: Vec<Extreme>,
+}
+
+#[derive(GraphQLObject)]
+pub struct Extreme {
+    /// The region code, matching `Location.region`
+    region: String,
+    extreme_type: ExtremeType,
+    value: f64,
+    units: String,
+    location_id: String,
+    location_name: String,
+}
+
+impl TryFrom<(&str, ExtremeReport)> for Extreme {
+    type Error = UkExtremesConversionError;
+
+    fn try_from((region, extreme): (&str, ExtremeReport)) -> Result<Self, Self::Error> {
+        Ok(Self {
+            region: region.to_string(),
+            extreme_type: ExtremeType::from_str(&extreme.extreme_type)?,
+            value: extreme.value.parse()?,
+            units: extreme.uom,
+            location_id: extreme.loc_id,
+            location_name: extreme.location_name,
+        })
+    }
+}
+
+impl TryFrom<UkExtremesResponse> for UkExtremes {
+    type Error = UkExtremesConversionError;
+
+    fn try_from(value: UkExtremesResponse) -> Result<Self, Self::Error> {
+        let UkEx {
+            extreme_date,
+            issued_at,
+            regions,
+        } = value.uk_ex;
+        let date = NaiveDate::parse_from_str(&extreme_date, "%Y-%m-%d")
+            .map_err(|_| UkExtremesConversionError::DateTimeParseError(extreme_date.clone()))?;
+        let issued_at = NaiveDateTime::parse_from_str(&issued_at, "%Y-%m-%dT%H:%M:%S")
+            .map_err(|_| UkExtremesConversionError::DateTimeParseError(issued_at.clone()))?;
+        let mut extremes = Vec::new();
+        for region in regions.region {
+            for extreme in region.extremes.extreme {
+                extremes.push(Extreme::try_from((region.id.as_str(), extreme))?);
+            }
+        }
+        Ok(Self {
+            date,
+            issued_at,
+            extremes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    #[test]
+    fn test_uk_extremes() {
+        let response: UkExtremesResponse =
+            serde_json::from_str(include_str!("../../fixtures/uk_extremes.json")).unwrap();
+        let uk_extremes: UkExtremes = response.try_into().unwrap();
+        assert_eq!(uk_extremes.date, NaiveDate::from_ymd(2020, 10, 16));
+        assert_eq!(uk_extremes.extremes.len(), 3);
+
+        let highest = &uk_extremes.extremes[0];
+        assert_eq!(highest.region, "ni");
+        assert_eq!(highest.extreme_type, ExtremeType::HighestMaximumTemperature);
+        assert_eq!(highest.value, 14.2);
+        assert_eq!(highest.units, "degC");
+        assert_eq!(highest.location_name, "Castlederg");
+
+        let rainfall = &uk_extremes.extremes[2];
+        assert_eq!(rainfall.region, "se");
+        assert_eq!(rainfall.extreme_type, ExtremeType::HighestRainfall);
+        assert_eq!(rainfall.units, "mm");
+    }
+}
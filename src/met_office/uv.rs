@@ -5,7 +5,7 @@ use thiserror::Error;
 
 type UvNumberType = i32;
 
-#[derive(Debug, Eq, PartialEq, Serialize, GraphQLObject)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, GraphQLObject)]
 pub struct UvIndex {
     index: UvNumberType,
     description: &'static str,
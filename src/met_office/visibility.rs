@@ -3,7 +3,7 @@ use serde::Serialize;
 use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(Debug, Eq, PartialEq, GraphQLEnum, Serialize)]
+#[derive(Clone, Debug, Eq, PartialEq, GraphQLEnum, Serialize)]
 pub enum Visibility {
     Unknown,
     VeryPoor,
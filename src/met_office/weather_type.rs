@@ -3,7 +3,7 @@ use serde::Serialize;
 use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(Debug, Eq, PartialEq, Serialize, GraphQLEnum)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, GraphQLEnum)]
 pub enum WeatherType {
     NotAvailable,
     ClearNight,
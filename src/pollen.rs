@@ -0,0 +1,219 @@
+//! Pollen forecasts scraped from the Met Office website, as DataPoint doesn't publish them
+mod pollen_level;
+
+use chrono::NaiveDate;
+use juniper::GraphQLObject;
+use scraper::{ElementRef, Html, Selector};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+use crate::met_office::{MetApi, MetApiError};
+pub use pollen_level::{PollenLevel, PollenLevelConversionError};
+
+/// Not yet checked against a saved copy of the live page, see `fixtures/pollen_forecast.html`
+const REGION_SELECTOR: &str = ".pollen-region[data-region]";
+const REGION_NAME_SELECTOR: &str = ".pollen-region-name";
+const DAY_SELECTOR: &str = ".pollen-day[data-date]";
+const LEVEL_SELECTOR: &str = ".pollen-level[data-level]";
+
+#[derive(Debug, Error)]
+pub enum PollenError {
+    #[error("could not fetch the pollen forecast page: {0}")]
+    Fetch(#[from] MetApiError),
+    #[error("Pollen Level Conversion Error: {0:?}")]
+    PollenLevelConversionError(#[from] PollenLevelConversionError),
+    #[error("Could not parse the date: {0}")]
+    DateTimeParseError(String),
+    #[error("missing {0} in the pollen forecast page")]
+    MissingElement(&'static str),
+    /// The page layout has most likely changed, so the selectors need updating
+    #[error("no regions found in the pollen forecast page")]
+    NoRegions,
+}
+
+type Result<T> = std::result::Result<T, PollenError>;
+
+/// The pollen forecast for a region, the region matches `Location.region`
+#[derive(Clone, Debug, GraphQLObject)]
+pub struct RegionPollen {
+    pub region: String,
+    name: String,
+    days: Vec<PollenDay>,
+}
+
+#[derive(Clone, Debug, GraphQLObject)]
+pub struct PollenDay {
+    date: NaiveDate,
+    level: PollenLevel,
+}
+
+fn selector(selector: &str) -> Selector {
+    Selector::parse(selector).expect("pollen selectors are valid css")
+}
+
+fn parse_day(day: ElementRef) -> Result<PollenDay> {
+    let date = day
+        .value()
+        .attr("data-date")
+        .ok_or(PollenError::MissingElement("date"))?;
+    let level = day
+        .select(&selector(LEVEL_SELECTOR))
+        .next()
+        .and_then(|level| level.value().attr("data-level"))
+        .ok_or(PollenError::MissingElement("pollen level"))?;
+    Ok(PollenDay {
+        date: NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| PollenError::DateTimeParseError(date.to_string()))?,
+        level: PollenLevel::from_str(level)?,
+    })
+}
+
+fn parse_region(region: ElementRef) -> Result<RegionPollen> {
+    let code = region
+        .value()
+        .attr("data-region")
+        .ok_or(PollenError::MissingElement("region"))?;
+    let name = region
+        .select(&selector(REGION_NAME_SELECTOR))
+        .next()
+        .ok_or(PollenError::MissingElement("region name"))?
+        .text()
+        .collect::<String>();
+    let days: Result<Vec<_>> = region
+        .select(&selector(DAY_SELECTOR))
+        .map(parse_day)
+        .collect();
+    Ok(RegionPollen {
+        region: code.to_string(),
+        name: name.trim().to_string(),
+        days: days?,
+    })
+}
+
+/// Parses the regional pollen forecasts out of the pollen forecast page
+pub fn parse_pollen_forecast(html: &str) -> Result<Vec<RegionPollen>> {
+    let document = Html::parse_document(html);
+    let regions = document
+        .select(&selector(REGION_SELECTOR))
+        .map(parse_region)
+        .collect::<Result<Vec<_>>>()?;
+    if regions.is_empty() {
+        return Err(PollenError::NoRegions);
+    }
+    Ok(regions)
+}
+
+/// The page and the regions parsed from it
+type ParsedPage = (Arc<str>, Arc<Vec<RegionPollen>>);
+
+/// Fetches the page through `MetApi`, so it is cached like DataPoint responses, and only parses
+/// it again once a new copy has been fetched
+pub struct PollenApi {
+    met_api: MetApi,
+    parsed: Mutex<Option<ParsedPage>>,
+}
+
+impl PollenApi {
+    pub fn new(met_api: MetApi) -> PollenApi {
+        PollenApi {
+            met_api,
+            parsed: Mutex::new(None),
+        }
+    }
+
+    pub async fn forecast(&self) -> Result<Arc<Vec<RegionPollen>>> {
+        let html = self.met_api.pollen_forecast_page().await?;
+        if let Some((page, regions)) = &*self.parsed.lock().unwrap() {
+            if Arc::ptr_eq(page, &html) {
+                return Ok(regions.clone());
+            }
+        }
+        let regions = Arc::new(parse_pollen_forecast(&html)?);
+        *self.parsed.lock().unwrap() = Some((html, regions.clone()));
+        Ok(regions)
+    }
+
+    pub async fn region_forecast(&self, region: &str) -> Result<Option<RegionPollen>> {
+        Ok(self
+            .forecast()
+            .await?
+            .iter()
+            .find(|pollen| pollen.region == region)
+            .cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::met_office::FakeTransport;
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_parse_pollen_forecast() {
+        let regions =
+            parse_pollen_forecast(include_str!("../fixtures/pollen_forecast.html")).unwrap();
+        assert_eq!(regions.len(), 2);
+
+        let south_east = &regions[0];
+        assert_eq!(south_east.region, "se");
+        assert_eq!(south_east.name, "London & South East England");
+        assert_eq!(south_east.days.len(), 3);
+        assert_eq!(south_east.days[0].date, NaiveDate::from_ymd(2020, 6, 20));
+        assert_eq!(south_east.days[0].level, PollenLevel::High);
+        assert_eq!(south_east.days[1].level, PollenLevel::VeryHigh);
+
+        let orkney = &regions[1];
+        assert_eq!(orkney.region, "os");
+        assert_eq!(orkney.days[2].level, PollenLevel::Moderate);
+    }
+
+    #[test]
+    fn test_missing_level() {
+        let html = r#"<div class="pollen-region" data-region="se">
+            <h3 class="pollen-region-name">London &amp; South East England</h3>
+            <li class="pollen-day" data-date="2020-06-20"></li>
+        </div>"#;
+        assert!(matches!(
+            parse_pollen_forecast(html),
+            Err(PollenError::MissingElement("pollen level"))
+        ));
+    }
+
+    #[test]
+    fn test_no_regions() {
+        assert!(matches!(
+            parse_pollen_forecast("<html></html>"),
+            Err(PollenError::NoRegions)
+        ));
+    }
+
+    #[test]
+    fn test_page_without_regions_is_an_error() {
+        let html = include_str!("../fixtures/pollen_forecast.html")
+            .replace("pollen-region", "pollen-area");
+        assert!(matches!(
+            parse_pollen_forecast(&html),
+            Err(PollenError::NoRegions)
+        ));
+    }
+
+    #[test]
+    fn test_page_is_fetched_once_for_every_region() {
+        let transport = FakeTransport::default()
+            .with("pollen", include_str!("../fixtures/pollen_forecast.html"));
+        let (met_api, transport) = MetApi::fake_with(transport, |config| {
+            config.pollen_url = format!("{}/pollen", FakeTransport::BASE_URL);
+        });
+        let pollen = PollenApi::new(met_api);
+
+        assert_eq!(block_on(pollen.forecast()).unwrap().len(), 2);
+        let orkney = block_on(pollen.region_forecast("os")).unwrap().unwrap();
+        assert_eq!(orkney.name, "Orkney & Shetland");
+        assert!(block_on(pollen.region_forecast("nowhere"))
+            .unwrap()
+            .is_none());
+        assert_eq!(transport.requests(), vec!["pollen"]);
+    }
+}
@@ -0,0 +1,53 @@
+use juniper::GraphQLEnum;
+use serde::Serialize;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, GraphQLEnum, Serialize)]
+pub enum PollenLevel {
+    Low,
+    Moderate,
+    High,
+    VeryHigh,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PollenLevelConversionError {
+    #[error("invalid pollen level code, found {0}")]
+    InvalidCode(String),
+}
+
+impl FromStr for PollenLevel {
+    type Err = PollenLevelConversionError;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        match code {
+            "L" => Ok(PollenLevel::Low),
+            "M" => Ok(PollenLevel::Moderate),
+            "H" => Ok(PollenLevel::High),
+            "VH" => Ok(PollenLevel::VeryHigh),
+            _ => Err(PollenLevelConversionError::InvalidCode(code.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_valid_pollen_level_codes() {
+        assert_eq!(PollenLevel::from_str("L").unwrap(), PollenLevel::Low);
+        assert_eq!(PollenLevel::from_str("M").unwrap(), PollenLevel::Moderate);
+        assert_eq!(PollenLevel::from_str("H").unwrap(), PollenLevel::High);
+        assert_eq!(PollenLevel::from_str("VH").unwrap(), PollenLevel::VeryHigh);
+    }
+
+    #[test]
+    fn test_unknown_code() {
+        assert_eq!(
+            PollenLevel::from_str("An invalid code").unwrap_err(),
+            PollenLevelConversionError::InvalidCode("An invalid code".to_string())
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Keeps the forecasts and observations of watched sites cached
+//!
+//! DataPoint publishes new data every hour, so shortly after each update the watched sites are
+//! fetched again and the cache is warm before clients ask for them.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{NaiveDateTime, Timelike, Utc};
+use juniper::GraphQLObject;
+use log::{info, warn};
+
+use crate::met_office::{LocationId, MetApi, MetApiError};
+
+/// How often the watch list is checked for sites that are due a refresh
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// The longest a site that keeps failing is left before it is tried again
+const MAX_BACKOFF: Duration = Duration::from_secs(30 * 60);
+
+#[derive(GraphQLObject)]
+pub struct WatchedLocation {
+    location: String,
+    /// When the site's forecast was last fetched, null until it has been
+    forecast_refreshed_at: Option<NaiveDateTime>,
+    /// When the site's observations were last fetched, null until they have been
+    observations_refreshed_at: Option<NaiveDateTime>,
+    /// When the site is tried again after its last refresh failed, null if it didn't fail
+    retry_at: Option<NaiveDateTime>,
+}
+
+/// What is kept cached for each watched site
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Data {
+    Forecast,
+    Observations,
+}
+
+/// When a watched site's data was last fetched, and how often refreshing it has failed since
+#[derive(Clone, Copy, Default)]
+struct Refreshes {
+    forecast: Option<NaiveDateTime>,
+    observations: Option<NaiveDateTime>,
+    failures: u32,
+    retry_at: Option<NaiveDateTime>,
+}
+
+impl Refreshes {
+    fn refreshed_at(&self, data: Data) -> Option<NaiveDateTime> {
+        match data {
+            Data::Forecast => self.forecast,
+            Data::Observations => self.observations,
+        }
+    }
+}
+
+/// How long to wait after `failures` refreshes in a row have failed, doubling from the check
+/// interval up to `MAX_BACKOFF`
+fn backoff(failures: u32) -> chrono::Duration {
+    let backoff = CHECK_INTERVAL
+        .checked_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .unwrap_or(MAX_BACKOFF);
+    chrono::Duration::from_std(backoff.min(MAX_BACKOFF)).unwrap()
+}
+
+/// The watched sites and when each was last refreshed
+#[derive(Default)]
+pub struct WatchList {
+    locations: Mutex<HashMap<LocationId, Refreshes>>,
+}
+
+impl WatchList {
+    pub fn new(location_ids: &[LocationId]) -> WatchList {
+        let watch_list = WatchList::default();
+        for location_id in location_ids {
+            watch_list.watch(*location_id);
+        }
+        watch_list
+    }
+
+    pub fn watch(&self, location_id: LocationId) {
+        self.locations
+            .lock()
+            .unwrap()
+            .entry(location_id)
+            .or_default();
+    }
+
+    pub fn unwatch(&self, location_id: LocationId) {
+        self.locations.lock().unwrap().remove(&location_id);
+    }
+
+    pub fn locations(&self) -> Vec<WatchedLocation> {
+        let locations = self.locations.lock().unwrap();
+        let mut watched: Vec<_> = locations
+            .iter()
+            .map(|(location_id, refreshes)| WatchedLocation {
+                location: location_id.to_string(),
+                forecast_refreshed_at: refreshes.forecast,
+                observations_refreshed_at: refreshes.observations,
+                retry_at: refreshes.retry_at,
+            })
+            .collect();
+        watched.sort_by(|a, b| a.location.cmp(&b.location));
+        watched
+    }
+
+    /// The data of each site that hasn't been refreshed since `since`, leaving out sites that are
+    /// backing off at `now`
+    fn due(&self, since: NaiveDateTime, now: NaiveDateTime) -> Vec<(LocationId, Vec<Data>)> {
+        self.locations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, refreshes)| refreshes.retry_at.filter(|at| *at > now).is_none())
+            .map(|(location_id, refreshes)| {
+                let due = [Data::Forecast, Data::Observations]
+                    .iter()
+                    .copied()
+                    .filter(|data| {
+                        refreshes
+                            .refreshed_at(*data)
+                            .filter(|at| *at >= since)
+                            .is_none()
+                    })
+                    .collect::<Vec<_>>();
+                (*location_id, due)
+            })
+            .filter(|(_, due)| !due.is_empty())
+            .collect()
+    }
+
+    /// Records the data that was fetched at `at`, and backs off if any of it failed. Only recorded
+    /// if the site is still watched.
+    fn refreshed(
+        &self,
+        location_id: LocationId,
+        refreshed: &[Data],
+        failed: bool,
+        at: NaiveDateTime,
+    ) {
+        let mut locations = self.locations.lock().unwrap();
+        let refreshes = match locations.get_mut(&location_id) {
+            Some(refreshes) => refreshes,
+            None => return,
+        };
+        for data in refreshed {
+            match data {
+                Data::Forecast => refreshes.forecast = Some(at),
+                Data::Observations => refreshes.observations = Some(at),
+            }
+        }
+        if failed {
+            refreshes.failures += 1;
+            refreshes.retry_at = Some(at + backoff(refreshes.failures));
+        } else {
+            refreshes.failures = 0;
+            refreshes.retry_at = None;
+        }
+    }
+}
+
+/// When the latest refresh was due, `delay` after the most recent hourly DataPoint update. A delay
+/// of an hour or more wraps around, as an update is published every hour.
+fn last_due(now: NaiveDateTime, delay: chrono::Duration) -> NaiveDateTime {
+    let delay = chrono::Duration::seconds(delay.num_seconds().rem_euclid(60 * 60));
+    let due = now.date().and_hms(now.hour(), 0, 0) + delay;
+    if due <= now {
+        due
+    } else {
+        due - chrono::Duration::hours(1)
+    }
+}
+
+/// Fetches through the cache, so an entry that is still fresh isn't fetched again. `false` when
+/// only a stale copy could be had.
+async fn refresh(
+    met_api: &MetApi,
+    location_id: LocationId,
+    data: Data,
+) -> Result<bool, MetApiError> {
+    match data {
+        Data::Forecast => Ok(!met_api.forecast(location_id, None).await?.is_stale()),
+        Data::Observations => match met_api.observations(location_id).await {
+            Ok(_) => Ok(true),
+            // Only some sites report observations, the others have nothing to keep cached
+            Err(error) if error.is_not_found() => Ok(true),
+            Err(error) => Err(error),
+        },
+    }
+}
+
+/// Refreshes whatever is due of each site, backing off from a site while its refreshes fail
+async fn refresh_due(watch_list: &WatchList, met_api: &MetApi, since: NaiveDateTime) {
+    for (location_id, due) in watch_list.due(since, Utc::now().naive_utc()) {
+        let mut refreshed = Vec::new();
+        let mut failed = false;
+        for data in due {
+            match refresh(met_api, location_id, data).await {
+                Ok(true) => refreshed.push(data),
+                Ok(false) => {
+                    info!("DataPoint is unavailable, {} is still stale", location_id);
+                    failed = true;
+                }
+                Err(error) => {
+                    warn!(
+                        "could not prefetch {:?} for {}: {}",
+                        data, location_id, error
+                    );
+                    failed = true;
+                }
+            }
+        }
+        watch_list.refreshed(location_id, &refreshed, failed, Utc::now().naive_utc());
+    }
+}
+
+/// Refreshes the sites that are due every minute, `delay` is how long after each hourly update
+/// DataPoint is asked for the new data
+pub async fn run(watch_list: Arc<WatchList>, met_api: MetApi, delay: Duration) {
+    let delay = chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+    let mut interval = actix_web::rt::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let since = last_due(Utc::now().naive_utc(), delay);
+        refresh_due(&watch_list, &met_api, since).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::met_office::FakeTransport;
+    use chrono::NaiveDate;
+    use futures::executor::block_on;
+
+    fn time(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2020, 10, 17).and_hms(hour, minute, 0)
+    }
+
+    #[test]
+    fn test_last_due() {
+        let delay = chrono::Duration::minutes(10);
+        assert_eq!(last_due(time(15, 20), delay), time(15, 10));
+        assert_eq!(last_due(time(15, 10), delay), time(15, 10));
+        assert_eq!(last_due(time(15, 5), delay), time(14, 10));
+
+        let delay = chrono::Duration::minutes(70);
+        assert_eq!(last_due(time(15, 20), delay), time(15, 10));
+        assert_eq!(last_due(time(15, 5), delay), time(14, 10));
+        assert!(last_due(time(15, 5), chrono::Duration::hours(3)) <= time(15, 5));
+    }
+
+    #[test]
+    fn test_due_locations() {
+        let watch_list =
+            WatchList::new(&[LocationId::Location(3840), LocationId::Location(310069)]);
+        assert_eq!(watch_list.due(time(15, 10), time(15, 11)).len(), 2);
+
+        watch_list.refreshed(
+            LocationId::Location(3840),
+            &[Data::Forecast, Data::Observations],
+            false,
+            time(15, 12),
+        );
+        watch_list.refreshed(
+            LocationId::Location(310069),
+            &[Data::Forecast],
+            false,
+            time(15, 12),
+        );
+        watch_list.refreshed(
+            LocationId::Location(1),
+            &[Data::Forecast],
+            false,
+            time(15, 12),
+        );
+        assert_eq!(
+            watch_list.due(time(15, 10), time(15, 13)),
+            vec![(LocationId::Location(310069), vec![Data::Observations])]
+        );
+        assert_eq!(watch_list.due(time(16, 10), time(16, 11)).len(), 2);
+
+        watch_list.unwatch(LocationId::Location(310069));
+        let locations = watch_list.locations();
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].location, "3840");
+        assert_eq!(locations[0].forecast_refreshed_at, Some(time(15, 12)));
+        assert_eq!(locations[0].observations_refreshed_at, Some(time(15, 12)));
+    }
+
+    #[test]
+    fn test_failures_back_off() {
+        assert_eq!(backoff(1), chrono::Duration::minutes(1));
+        assert_eq!(backoff(3), chrono::Duration::minutes(4));
+        assert_eq!(backoff(10), chrono::Duration::minutes(30));
+        assert_eq!(backoff(u32::MAX), chrono::Duration::minutes(30));
+
+        let location_id = LocationId::Location(3840);
+        let watch_list = WatchList::new(&[location_id]);
+        watch_list.refreshed(location_id, &[], true, time(15, 10));
+        watch_list.refreshed(location_id, &[], true, time(15, 11));
+        assert!(watch_list.due(time(15, 10), time(15, 12)).is_empty());
+        assert_eq!(watch_list.due(time(15, 10), time(15, 13)).len(), 1);
+        assert_eq!(watch_list.locations()[0].retry_at, Some(time(15, 13)));
+
+        watch_list.refreshed(location_id, &[Data::Forecast], false, time(15, 13));
+        assert_eq!(watch_list.locations()[0].retry_at, None);
+    }
+
+    #[test]
+    fn test_refresh_due() {
+        let transport = FakeTransport::default()
+            .with(
+                "val/wxfcs/all/json/310069?res=3hourly",
+                include_str!("../fixtures/forecast.json"),
+            )
+            .with_status("val/wxfcs/all/json/3840?res=3hourly", 503)
+            .with(
+                "val/wxobs/all/json/3840?res=hourly",
+                include_str!("../fixtures/observations.json"),
+            );
+        let (met_api, _) = MetApi::fake(transport);
+        let watch_list =
+            WatchList::new(&[LocationId::Location(3840), LocationId::Location(310069)]);
+        let since = Utc::now().naive_utc() - chrono::Duration::minutes(1);
+        block_on(refresh_due(&watch_list, &met_api, since));
+
+        let locations = watch_list.locations();
+        // 310069 has no observations, which leaves nothing to retry
+        assert_eq!(locations[0].location, "310069");
+        assert!(locations[0].forecast_refreshed_at.is_some());
+        assert!(locations[0].observations_refreshed_at.is_some());
+        assert!(locations[0].retry_at.is_none());
+        // The forecast failed, so 3840 backs off before only its forecast is fetched again
+        assert!(locations[1].forecast_refreshed_at.is_none());
+        assert!(locations[1].observations_refreshed_at.is_some());
+        assert!(locations[1].retry_at.is_some());
+        assert!(watch_list.due(since, Utc::now().naive_utc()).is_empty());
+    }
+}
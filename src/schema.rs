@@ -1,33 +1,424 @@
-use crate::met_office::{Forecast, Location, LocationId, MetApi};
-use juniper::FieldResult;
-use juniper::RootNode;
+use crate::api_error::{ApiError, ApiResult};
+use crate::auth::{Principal, Scope};
+use crate::met_office::{
+    CacheStats, Capabilities, DailyForecast, Forecast, Location, LocationForecasts, LocationId,
+    MapLayer, MetApi, MountainArea, MountainForecast, NationalPark, NationalParkForecast,
+    Observation, Quota, Region, RegionalForecast, SurfacePressureChart, UkExtremes,
+};
+use crate::pollen::{PollenApi, RegionPollen};
+use crate::prefetch::{WatchList, WatchedLocation};
+use chrono::{NaiveDate, NaiveDateTime};
+use juniper::{graphql_object, EmptySubscription, RootNode};
 use std::str::FromStr;
+use std::sync::Arc;
+
+pub struct Context {
+    pub met_api: MetApi,
+    pub principal: Principal,
+    pub watch_list: Arc<WatchList>,
+    pub pollen: Arc<PollenApi>,
+}
+
+impl Context {
+    fn require(&self, scope: Scope) -> ApiResult<()> {
+        if self.principal.has_scope(scope) {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden(scope))
+        }
+    }
+
+    /// The `apiKey` argument is deprecated, the server's keys are used when it isn't given
+    fn met_api(&self, scope: Scope, api_key: Option<String>) -> ApiResult<MetApi> {
+        self.require(scope)?;
+        Ok(match api_key {
+            Some(api_key) => self.met_api.with_api_key(api_key),
+            None => self.met_api.clone(),
+        })
+    }
+}
+
+impl juniper::Context for Context {}
 
 pub struct QueryRoot;
 
-#[juniper::object]
+#[graphql_object(context = Context)]
 impl QueryRoot {
-    fn api_version() -> &str {
+    fn api_version() -> &'static str {
         "0.1.0"
     }
 
-    fn locations(api_key: String) -> FieldResult<Vec<Location>> {
-        Ok(MetApi::new(api_key).forecast_site_list()?) // ToDo: Don't leak the error
+    async fn locations(context: &Context, api_key: Option<String>) -> ApiResult<Vec<Location>> {
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.forecast_site_list().await?)
+    }
+
+    async fn capabilities(context: &Context, api_key: Option<String>) -> ApiResult<Capabilities> {
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.forecast_capabilities().await?)
+    }
+
+    async fn forecast(
+        context: &Context,
+        api_key: Option<String>,
+        location: String,
+        time: Option<NaiveDateTime>,
+    ) -> ApiResult<Vec<Forecast>> {
+        let location_id = LocationId::from_str(&location)?;
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.forecast(location_id, time).await?.0)
     }
 
-    fn forecast(api_key: String, location: String) -> FieldResult<Vec<Forecast>> {
+    async fn site_forecasts(
+        context: &Context,
+        api_key: Option<String>,
+        location: String,
+        time: Option<NaiveDateTime>,
+    ) -> ApiResult<Vec<LocationForecasts>> {
         let location_id = LocationId::from_str(&location)?;
-        Ok(MetApi::new(api_key).forecast(location_id)?.0) // ToDo: Don't leak the error
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.site_forecasts(location_id, time).await?)
+    }
+
+    async fn daily_forecast(
+        context: &Context,
+        api_key: Option<String>,
+        location: String,
+    ) -> ApiResult<Vec<DailyForecast>> {
+        let location_id = LocationId::from_str(&location)?;
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.daily_forecast(location_id).await?.0)
+    }
+
+    async fn observation_sites(
+        context: &Context,
+        api_key: Option<String>,
+    ) -> ApiResult<Vec<Location>> {
+        let met_api = context.met_api(Scope::Observations, api_key)?;
+        Ok(met_api.observation_site_list().await?)
+    }
+
+    async fn observations(
+        context: &Context,
+        api_key: Option<String>,
+        location: String,
+    ) -> ApiResult<Vec<Observation>> {
+        let location_id = LocationId::from_str(&location)?;
+        let met_api = context.met_api(Scope::Observations, api_key)?;
+        Ok(met_api.observations(location_id).await?.0)
+    }
+
+    async fn regional_forecast_sites(
+        context: &Context,
+        api_key: Option<String>,
+    ) -> ApiResult<Vec<Region>> {
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.regional_forecast_site_list().await?)
+    }
+
+    async fn regional_forecast(
+        context: &Context,
+        api_key: Option<String>,
+        region: String,
+    ) -> ApiResult<Option<RegionalForecast>> {
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.regional_forecast_by_name(&region).await?)
+    }
+
+    async fn national_parks(
+        context: &Context,
+        api_key: Option<String>,
+    ) -> ApiResult<Vec<NationalPark>> {
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.national_park_site_list().await?)
+    }
+
+    async fn national_park_forecast(
+        context: &Context,
+        api_key: Option<String>,
+        park: String,
+    ) -> ApiResult<NationalParkForecast> {
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.national_park_forecast(&park).await?)
+    }
+
+    async fn mountain_areas(
+        context: &Context,
+        api_key: Option<String>,
+    ) -> ApiResult<Vec<MountainArea>> {
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.mountain_area_site_list().await?)
+    }
+
+    async fn mountain_forecast(
+        context: &Context,
+        api_key: Option<String>,
+        area: String,
+    ) -> ApiResult<MountainForecast> {
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.mountain_area_forecast(&area).await?)
+    }
+
+    async fn forecast_layers(
+        context: &Context,
+        api_key: Option<String>,
+    ) -> ApiResult<Vec<MapLayer>> {
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.forecast_layers().await?)
+    }
+
+    async fn observation_layers(
+        context: &Context,
+        api_key: Option<String>,
+    ) -> ApiResult<Vec<MapLayer>> {
+        let met_api = context.met_api(Scope::Observations, api_key)?;
+        Ok(met_api.observation_layers().await?)
+    }
+
+    async fn surface_pressure_charts(
+        context: &Context,
+        api_key: Option<String>,
+    ) -> ApiResult<Vec<SurfacePressureChart>> {
+        let met_api = context.met_api(Scope::Forecast, api_key)?;
+        Ok(met_api.surface_pressure_charts().await?)
+    }
+
+    /// How well the DataPoint response cache is doing
+    fn cache_stats(context: &Context) -> ApiResult<CacheStats> {
+        context.require(Scope::Admin)?;
+        Ok(context.met_api.cache_stats())
+    }
+
+    /// The requests left for each DataPoint API key
+    fn quota(context: &Context, api_key: Option<String>) -> ApiResult<Vec<Quota>> {
+        Ok(context.met_api(Scope::Admin, api_key)?.quota())
+    }
+
+    /// The sites whose forecasts and observations are fetched after each DataPoint update
+    fn watched_locations(context: &Context) -> ApiResult<Vec<WatchedLocation>> {
+        context.require(Scope::Admin)?;
+        Ok(context.watch_list.locations())
+    }
+
+    async fn pollen(context: &Context) -> ApiResult<Vec<RegionPollen>> {
+        context.require(Scope::Forecast)?;
+        Ok(context.pollen.forecast().await?.to_vec())
+    }
+
+    /// DataPoint only publishes the latest extremes, so any other date returns nothing
+    async fn uk_extremes(
+        context: &Context,
+        api_key: Option<String>,
+        date: Option<NaiveDate>,
+    ) -> ApiResult<Option<UkExtremes>> {
+        let met_api = context.met_api(Scope::Observations, api_key)?;
+        let uk_extremes = met_api.uk_extremes().await?;
+        match date {
+            Some(date) if date != uk_extremes.date => Ok(None),
+            _ => Ok(Some(uk_extremes)),
+        }
+    }
+}
+
+#[graphql_object(context = Context)]
+impl Location {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn latitude(&self) -> &str {
+        &self.latitude
+    }
+
+    fn longitude(&self) -> &str {
+        &self.longitude
+    }
+
+    fn elevation(&self) -> Option<&str> {
+        self.elevation.as_deref()
+    }
+
+    fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    fn unitary_auth_area(&self) -> Option<&str> {
+        self.unitary_auth_area.as_deref()
+    }
+
+    /// Whether DataPoint was unavailable and this is the last site list fetched from it
+    fn stale(&self) -> bool {
+        self.stale
+    }
+
+    /// When the site was fetched from DataPoint
+    fn fetched_at(&self) -> Option<NaiveDateTime> {
+        self.fetched_at
+    }
+
+    async fn regional_forecast(
+        &self,
+        context: &Context,
+        api_key: Option<String>,
+    ) -> ApiResult<Option<RegionalForecast>> {
+        match &self.region {
+            Some(region) => {
+                let met_api = context.met_api(Scope::Forecast, api_key)?;
+                Ok(met_api.regional_forecast_by_name(region).await?)
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn pollen(&self, context: &Context) -> ApiResult<Option<RegionPollen>> {
+        context.require(Scope::Forecast)?;
+        match &self.region {
+            Some(region) => Ok(context.pollen.region_forecast(region).await?),
+            None => Ok(None),
+        }
+    }
+}
+
+#[graphql_object(context = Context)]
+impl LocationForecasts {
+    fn location(&self) -> &Location {
+        &self.location
+    }
+
+    fn forecasts(&self) -> &[Forecast] {
+        &self.forecasts
     }
 }
 
 pub struct MutationRoot;
 
-#[juniper::object]
-impl MutationRoot {}
+#[graphql_object(context = Context)]
+impl MutationRoot {
+    /// Keeps the site's forecasts and observations cached, fetching them after each update
+    fn watch_location(context: &Context, location: String) -> ApiResult<Vec<WatchedLocation>> {
+        context.require(Scope::Admin)?;
+        context.watch_list.watch(LocationId::from_str(&location)?);
+        Ok(context.watch_list.locations())
+    }
+
+    fn unwatch_location(context: &Context, location: String) -> ApiResult<Vec<WatchedLocation>> {
+        context.require(Scope::Admin)?;
+        context.watch_list.unwatch(LocationId::from_str(&location)?);
+        Ok(context.watch_list.locations())
+    }
+}
 
-pub type Schema = RootNode<'static, QueryRoot, MutationRoot>;
+pub type Schema = RootNode<'static, QueryRoot, MutationRoot, EmptySubscription<Context>>;
 
 pub fn create_schema() -> Schema {
-    Schema::new(QueryRoot {}, MutationRoot {})
+    Schema::new(QueryRoot {}, MutationRoot {}, EmptySubscription::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::AccessTokens;
+    use crate::met_office::FakeTransport;
+    use futures::executor::block_on;
+    use juniper::Variables;
+    use serde_json::{json, Value};
+
+    fn context(principal: Principal) -> Context {
+        let transport = FakeTransport::default()
+            .with(
+                "val/wxfcs/all/json/sitelist",
+                include_str!("../fixtures/forecast_sitelist.json"),
+            )
+            .with(
+                "val/wxfcs/all/json/310069?res=3hourly",
+                include_str!("../fixtures/forecast.json"),
+            );
+        let (met_api, _) = MetApi::fake(transport);
+        Context {
+            pollen: Arc::new(PollenApi::new(met_api.clone())),
+            met_api,
+            principal,
+            watch_list: Arc::new(WatchList::default()),
+        }
+    }
+
+    fn execute(query: &str, context: &Context) -> Value {
+        let (data, errors) = block_on(juniper::execute(
+            query,
+            None,
+            &create_schema(),
+            &Variables::new(),
+            context,
+        ))
+        .unwrap();
+        json!({
+            "data": serde_json::to_value(&data).unwrap(),
+            "errors": serde_json::to_value(&errors).unwrap(),
+        })
+    }
+
+    fn unrestricted() -> Principal {
+        AccessTokens::disabled().principal(None).unwrap()
+    }
+
+    #[test]
+    fn test_locations_query() {
+        let result = execute(
+            "{ locations { id name region stale } }",
+            &context(unrestricted()),
+        );
+        assert_eq!(result["errors"], json!([]));
+        assert_eq!(
+            result["data"]["locations"][1],
+            json!({"id": "3840", "name": "Dunkeswell Aerodrome", "region": "sw", "stale": false})
+        );
+    }
+
+    #[test]
+    fn test_forecast_query() {
+        let result = execute(
+            r#"{ forecast(location: "310069") { timestamp temperature windDirection } }"#,
+            &context(unrestricted()),
+        );
+        assert_eq!(result["errors"], json!([]));
+        let forecasts = result["data"]["forecast"].as_array().unwrap();
+        assert_eq!(forecasts.len(), 3);
+        // Juniper 0.15 sends `NaiveDateTime` as seconds since the epoch, this is 15:00
+        assert_eq!(forecasts[0]["timestamp"], 1602946800.0);
+        assert_eq!(forecasts[0]["temperature"], 13);
+    }
+
+    #[test]
+    fn test_errors_carry_a_code() {
+        let result = execute(
+            r#"{ forecast(location: "3840") { temperature } }"#,
+            &context(unrestricted()),
+        );
+        assert_eq!(result["errors"][0]["extensions"]["code"], "NOT_FOUND");
+
+        let result = execute("{ locations { id } }", &context(Principal::anonymous()));
+        assert_eq!(result["errors"][0]["extensions"]["code"], "FORBIDDEN");
+    }
+
+    #[test]
+    fn test_watch_location_mutations() {
+        let context = context(unrestricted());
+        let result = execute(
+            r#"mutation { watchLocation(location: "310069") { location forecastRefreshedAt retryAt } }"#,
+            &context,
+        );
+        assert_eq!(
+            result["data"]["watchLocation"],
+            json!([{"location": "310069", "forecastRefreshedAt": null, "retryAt": null}])
+        );
+        let result = execute(
+            r#"mutation { unwatchLocation(location: "310069") { location } }"#,
+            &context,
+        );
+        assert_eq!(result["data"]["unwatchLocation"], json!([]));
+    }
 }